@@ -25,24 +25,57 @@ pub enum Commands {
     Status,
 
     /// Show the snapshot log
-    Log {
-        /// Show full history graph (all parents) instead of linear first-parent view
-        #[arg(short, long)]
-        expand: bool,
-    },
+    Log(LogArgs),
+
+    /// Generate release notes for a range of snapshots, grouped by context
+    Changelog(ChangelogArgs),
+
+    /// Show which snapshot last changed each line of a file
+    Blame(BlameArgs),
+
+    /// Show every snapshot that changed a single function, class or other block
+    History(HistoryArgs),
 
-    /// Restore the workspace to a specific snapshot or track
-    Checkout { target: String },
+    /// Find functions, classes and other definitions by name across a snapshot
+    Symbols(SymbolsArgs),
 
-    /// Semantically compare a file with its incoming version during a conflict
-    Diff { file: PathBuf },
+    /// Search the files of a snapshot, or the lines added across a range of snapshots
+    Grep(GrepArgs),
+
+    /// Restore the workspace to a specific track, label or snapshot
+    Checkout(CheckoutArgs),
+
+    /// Rewrite files or directories from a snapshot, discarding their changes, without moving HEAD
+    Restore(RestoreArgs),
+
+    /// Semantically compare a file with its incoming version during a conflict, or list the
+    /// symbols changed between revisions
+    Diff(DiffArgs),
 
     /// Integrate a track into the current track
     Integrate(IntegrateArgs),
 
+    /// Apply the change made by a single snapshot onto the current HEAD
+    Port(PortArgs),
+
+    /// Record a new snapshot that undoes the change made by a snapshot
+    Revert(RevertArgs),
+
+    /// Binary-search history for the snapshot that introduced a bug
+    Bisect(BisectArgs),
+
+    /// Set uncommitted work aside and bring it back later
+    Shelve(ShelveArgs),
+
     /// Manage tracks (branches)
     Track(TrackArgs),
 
+    /// Manage labels (fixed names for snapshots)
+    Label(LabelArgs),
+
+    /// Inspect the contexts available for snapshots
+    Context(ContextArgs),
+
     /// Low-level plumbing tools
     Tools(ToolArgs),
 }
@@ -53,10 +86,188 @@ pub struct InitArgs {
 }
 
 #[derive(Args)]
-pub struct IntegrateArgs {
-    /// The target track or snapshot to integrate
+pub struct LogArgs {
+    /// Show full history graph (all parents) instead of linear first-parent view
+    #[arg(short, long)]
+    pub expand: bool,
+
+    /// Only show snapshots with this context (repeat to accept several)
+    #[arg(short, long = "context", value_name = "CONTEXT")]
+    pub contexts: Vec<SnapshotContext>,
+
+    /// Only show snapshots whose author contains this text (case-insensitive)
+    #[arg(long)]
+    pub author: Option<String>,
+
+    /// Only show snapshots taken on or after this date (YYYY-MM-DD, RFC 3339 or Unix timestamp)
+    #[arg(long, value_name = "DATE")]
+    pub since: Option<String>,
+
+    /// Only show snapshots taken on or before this date (YYYY-MM-DD, RFC 3339 or Unix timestamp)
+    #[arg(long, value_name = "DATE")]
+    pub until: Option<String>,
+
+    /// Only show snapshots whose message matches this regular expression
+    #[arg(long, value_name = "PATTERN")]
+    pub grep: Option<String>,
+
+    /// Only show snapshots that changed this file or directory
+    #[arg(long)]
+    pub path: Option<PathBuf>,
+
+    /// Show at most this many snapshots
+    #[arg(short = 'n', long)]
+    pub limit: Option<usize>,
+
+    /// Show each snapshot on a single line
+    #[arg(long, group = "output")]
+    pub oneline: bool,
+
+    /// Format each snapshot with a template, e.g. "{short} {context}: {summary}"
+    #[arg(long, group = "output", value_name = "TEMPLATE")]
+    pub format: Option<String>,
+
+    /// Print the matching snapshots as a JSON array
+    #[arg(long, group = "output")]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct ChangelogArgs {
+    /// The snapshots to describe, as `<from>..<to>` (either side defaults to HEAD)
+    pub range: String,
+
+    /// Print the changelog as JSON instead of Markdown
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct BlameArgs {
+    /// The file to blame
+    pub path: PathBuf,
+
+    /// Blame the file as recorded by this revision instead of HEAD
+    #[arg(long, value_name = "REVISION")]
+    pub rev: Option<String>,
+
+    /// Attribute lines brought in by integrations to the snapshots that wrote them
+    #[arg(long)]
+    pub follow_merges: bool,
+}
+
+#[derive(Args)]
+pub struct SymbolsArgs {
+    /// Part of a symbol name, or a symbol path such as 'impl Parser > parse'
+    pub query: String,
+
+    /// Search the snapshot recorded by this revision instead of HEAD
+    #[arg(long, value_name = "REVISION")]
+    pub rev: Option<String>,
+}
+
+#[derive(Args)]
+pub struct CheckoutArgs {
+    /// A track, label or snapshot
+    #[arg(required_unless_present_any = ["continue", "abort"])]
     pub target: Option<String>,
 
+    /// Three-way merge uncommitted changes onto the target, even where the target changed the
+    /// same files
+    #[arg(short, long)]
+    pub merge: bool,
+
+    /// Finish a merged checkout after resolving conflicts
+    #[arg(long, conflicts_with_all = ["target", "merge", "abort"])]
+    pub r#continue: bool,
+
+    /// Abort a merged checkout and return to the previous HEAD and workspace
+    #[arg(long, conflicts_with_all = ["target", "merge"])]
+    pub abort: bool,
+}
+
+#[derive(Args)]
+pub struct RestoreArgs {
+    /// Restore from this revision instead of HEAD
+    #[arg(long, value_name = "REVISION")]
+    pub source: Option<String>,
+
+    /// The files and directories to restore
+    #[arg(required = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct GrepArgs {
+    /// Regular expression to search for
+    pub pattern: String,
+
+    /// Search the snapshot recorded by this revision instead of HEAD
+    #[arg(conflicts_with = "range")]
+    pub revision: Option<String>,
+
+    /// Search the lines each snapshot added, oldest first, in a <from>..<to> range or in the
+    /// whole history of a revision
+    #[arg(long, value_name = "RANGE")]
+    pub range: Option<String>,
+
+    /// Match case-insensitively
+    #[arg(short, long)]
+    pub ignore_case: bool,
+
+    /// Treat the pattern as a literal string
+    #[arg(short = 'F', long)]
+    pub fixed_strings: bool,
+
+    /// Only search these files and directories
+    #[arg(last = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// List the functions, classes and other blocks changed between two revisions.
+    /// With a single revision, compare it with its first parent
+    #[arg(long, num_args = 1..=2, value_names = ["REV1", "REV2"])]
+    pub semantic: Option<Vec<String>>,
+
+    /// The conflicted file, or with --semantic the paths to compare (after `--`)
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct HistoryArgs {
+    /// The file containing the symbol
+    pub path: PathBuf,
+
+    /// The block to follow, as a `>`-separated path such as 'impl Hydrator > apply_diff'
+    #[arg(long)]
+    pub symbol: String,
+
+    /// Start from this revision instead of HEAD
+    #[arg(long, value_name = "REVISION")]
+    pub rev: Option<String>,
+}
+
+#[derive(Args)]
+pub struct IntegrateArgs {
+    /// The target revisions to integrate (tracks, labels or snapshots).
+    /// Several targets are integrated together into a single merge snapshot.
+    pub targets: Vec<String>,
+
+    /// Always create a merge snapshot, even when a fast-forward is possible
+    #[arg(long, conflicts_with = "ff_only")]
+    pub no_ff: bool,
+
+    /// Refuse to integrate unless the current track can be fast-forwarded
+    #[arg(long)]
+    pub ff_only: bool,
+
+    /// Report what the integration would do without touching the workspace or refs.
+    /// Exits with an error if it would conflict.
+    #[arg(long, conflicts_with_all = ["continue", "abort"])]
+    pub dry_run: bool,
+
     /// Continue integration after resolving conflicts
     #[arg(long)]
     pub r#continue: bool,
@@ -66,15 +277,56 @@ pub struct IntegrateArgs {
     pub abort: bool,
 }
 
+#[derive(Args)]
+pub struct PortArgs {
+    /// The snapshot to port (any revision)
+    pub revision: Option<String>,
+
+    /// Continue porting after resolving conflicts
+    #[arg(long)]
+    pub r#continue: bool,
+
+    /// Abort the current port and return to previous state
+    #[arg(long)]
+    pub abort: bool,
+}
+
+#[derive(Args)]
+pub struct RevertArgs {
+    /// The snapshot to revert (any revision)
+    pub revision: Option<String>,
+
+    /// For merge snapshots, the parent (starting at 1) whose side is kept;
+    /// the changes brought in by the other parents are reverted
+    #[arg(short, long)]
+    pub mainline: Option<usize>,
+
+    /// Continue reverting after resolving conflicts
+    #[arg(long)]
+    pub r#continue: bool,
+
+    /// Abort the current revert and return to previous state
+    #[arg(long)]
+    pub abort: bool,
+}
+
 #[derive(Args)]
 pub struct SnapshotArgs {
     /// Description of the changes
-    #[arg(short, long)]
-    pub message: String,
+    #[arg(short, long, required_unless_present = "suggest_context")]
+    pub message: Option<String>,
 
-    /// The intent of these changes
-    #[arg(short, long, value_enum)]
-    pub context: SnapshotContext,
+    /// The intent of these changes (see `jogen context list`)
+    #[arg(short, long, required_unless_present = "suggest_context")]
+    pub context: Option<SnapshotContext>,
+
+    /// Propose a context for the workspace changes, with the reason, without taking a snapshot
+    #[arg(long, conflicts_with = "context")]
+    pub suggest_context: bool,
+
+    /// Link the snapshot to a task (repeat to link several)
+    #[arg(short, long = "task", value_name = "TASK")]
+    pub tasks: Vec<String>,
 }
 
 #[derive(Args)]
@@ -96,6 +348,89 @@ pub enum TrackSubcommands {
     },
 }
 
+#[derive(Args)]
+pub struct LabelArgs {
+    #[command(subcommand)]
+    pub command: LabelSubcommands,
+}
+
+#[derive(Subcommand)]
+pub enum LabelSubcommands {
+    /// List all labels
+    List,
+    /// Create a new label
+    Create {
+        name: String,
+        /// The revision to label (defaults to HEAD)
+        revision: Option<String>,
+    },
+}
+
+#[derive(Args)]
+pub struct BisectArgs {
+    #[command(subcommand)]
+    pub command: BisectSubcommands,
+}
+
+#[derive(Subcommand)]
+pub enum BisectSubcommands {
+    /// Start bisecting between a bad revision and one or more good ones
+    Start {
+        /// A revision that has the bug
+        bad: String,
+        /// Revisions that do not have the bug
+        #[arg(required = true)]
+        good: Vec<String>,
+    },
+    /// Mark a snapshot as good (defaults to HEAD)
+    Good { revision: Option<String> },
+    /// Mark a snapshot as bad (defaults to HEAD)
+    Bad { revision: Option<String> },
+    /// Mark a snapshot as untestable (defaults to HEAD)
+    Skip { revision: Option<String> },
+    /// Test snapshots automatically: exit status 0 is good, 125 skips, 1-127 is bad
+    Run {
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// Stop bisecting and return to the snapshot or track bisect started from
+    Reset,
+}
+
+#[derive(Args)]
+pub struct ShelveArgs {
+    #[command(subcommand)]
+    pub command: ShelveSubcommands,
+}
+
+#[derive(Subcommand)]
+pub enum ShelveSubcommands {
+    /// Save the workspace's uncommitted changes as a shelf and restore the workspace to HEAD
+    Push {
+        /// Describe the work being shelved
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+    /// List shelves, newest first
+    List,
+    /// Merge a shelf's changes into the workspace (defaults to the newest shelf)
+    Apply { id: Option<usize> },
+    /// Delete a shelf (defaults to the newest shelf)
+    Drop { id: Option<usize> },
+}
+
+#[derive(Args)]
+pub struct ContextArgs {
+    #[command(subcommand)]
+    pub command: ContextSubcommands,
+}
+
+#[derive(Subcommand)]
+pub enum ContextSubcommands {
+    /// List built-in and configured contexts
+    List,
+}
+
 #[derive(Args)]
 pub struct ToolArgs {
     #[command(subcommand)]
@@ -116,4 +451,6 @@ pub enum ToolSubcommands {
     WriteSnapshot,
     /// Read a snapshot object
     ReadSnapshot { hash: String },
+    /// Rebuild the commit-graph cache from every track, label and HEAD
+    WriteGraph,
 }
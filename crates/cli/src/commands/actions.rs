@@ -1,16 +1,28 @@
+use std::collections::{HashMap, HashSet};
+
 use anyhow::Result;
 use chrono::Utc;
 use colored::*;
 
 use crate::{
-    args::{InitArgs, IntegrateArgs, SnapshotArgs},
+    args::{CheckoutArgs, InitArgs, IntegrateArgs, RestoreArgs, SnapshotArgs},
     commands::JogenRepo,
 };
 
 use jogen_core::{
+    config::Config,
+    graph::GraphTraversal,
+    hydrator::Hydrator,
     indexer::Indexer,
+    merge::MergeConflict,
+    ref_store::{CheckoutOrigin, IntegrationOperation, IntegrationStatus},
+    resolution_cache::ResolutionCache,
+    semantic::{SemanticBlock, SemanticEngine},
+    semantic_diff::{diff_blocks, SemanticDiff, SymbolChange, SymbolChangeKind},
+    suggest::ContextSuggester,
+    tree::{ChangeKind, TreeChange, TreeReader},
     object_store::ObjectType,
-    objects::{snapshot::Snapshot, JogenObject},
+    objects::snapshot::Snapshot,
 };
 
 pub fn handle(args: InitArgs) -> Result<()> {
@@ -23,6 +35,22 @@ pub fn handle(args: InitArgs) -> Result<()> {
 
 pub fn snapshot(args: SnapshotArgs) -> Result<()> {
     let repo = JogenRepo::from_cwd()?;
+    let config = Config::load(&repo.root_path)?;
+
+    if args.suggest_context {
+        return suggest_context(&repo, &config, args.message.as_deref());
+    }
+    let (Some(context), Some(message)) = (args.context, args.message) else {
+        return Err(anyhow::anyhow!("Must provide a context and a message."));
+    };
+
+    if let Some(status) = repo.ref_store.get_integration_status()? {
+        return Err(anyhow::anyhow!(
+            "A {} is in progress. Finish it with 'jogen {} --continue' or cancel it with '--abort' before taking a snapshot.",
+            status.operation.as_str(),
+            status.operation.as_str()
+        ));
+    }
 
     println!("{}", "Scanning workspace...".dimmed());
     let indexer = Indexer::new(&repo.object_store, &repo.root_path);
@@ -35,18 +63,25 @@ pub fn snapshot(args: SnapshotArgs) -> Result<()> {
         None => vec![],
     };
 
-    let snapshot_obj = Snapshot::new(
-        tree_hash,
-        parent_hashes.clone(),
-        "Jogen User <user@jogen.com>".to_string(),
-        Utc::now().timestamp(),
-        args.context,
-        args.message,
+    if let Some(task) = args.tasks.iter().find(|task| task.trim().is_empty() || task.contains('\n')) {
+        return Err(anyhow::anyhow!("Invalid task link {:?}: must be a single non-empty line", task));
+    }
+
+    let snapshot_obj = args.tasks.iter().fold(
+        Snapshot::new(
+            tree_hash,
+            parent_hashes.clone(),
+            "Jogen User <user@jogen.com>".to_string(),
+            Utc::now().timestamp(),
+            context,
+            message,
+        ),
+        |snapshot, task| snapshot.with_header("task", task.trim()),
     );
 
-    let snapshot_hash = repo
-        .object_store
-        .write_object(snapshot_obj.serialize()?.as_ref(), ObjectType::Snapshot)?;
+    config.validate(&snapshot_obj)?;
+
+    let snapshot_hash = repo.write_snapshot(&snapshot_obj)?;
 
     repo.ref_store.update_head(&snapshot_hash)?;
 
@@ -65,6 +100,65 @@ pub fn snapshot(args: SnapshotArgs) -> Result<()> {
     Ok(())
 }
 
+/// Proposes a context for the changes between HEAD and the workspace.
+fn suggest_context(repo: &JogenRepo, config: &Config, message: Option<&str>) -> Result<()> {
+    let head_tree = match repo.ref_store.read_head()? {
+        Some(hash) => Some(repo.load_snapshot(&hash)?.directory_hash),
+        None => None,
+    };
+
+    println!("{}", "Scanning workspace...".dimmed());
+    let workspace_tree = Indexer::new(&repo.object_store, &repo.root_path).index_path(&repo.root_path)?;
+
+    let engine = SemanticEngine::new()?;
+    let files = SemanticDiff::new(&repo.object_store, &engine).diff_trees(
+        head_tree.as_deref(),
+        workspace_tree.as_deref(),
+        &[],
+    )?;
+    if files.is_empty() {
+        println!("{} Nothing changed since the last snapshot.", "ℹ".blue());
+        return Ok(());
+    }
+
+    let symbols: Vec<&SymbolChange> = files.iter().filter_map(|file| file.symbols.as_ref()).flatten().collect();
+    let count = |kind: SymbolChangeKind| symbols.iter().filter(|change| change.kind == kind).count();
+    println!(
+        "{} {} file(s) changed: {} symbol(s) added, {} removed, {} modified, {} moved",
+        "⚙".blue(),
+        files.len(),
+        count(SymbolChangeKind::Added),
+        count(SymbolChangeKind::Removed),
+        count(SymbolChangeKind::Modified),
+        count(SymbolChangeKind::Moved)
+    );
+
+    if head_tree.is_none() {
+        println!("\nSuggested context: {}", "initial".yellow().bold());
+        println!("Reason:            this is the first snapshot");
+        return Ok(());
+    }
+
+    let Some(suggestion) = ContextSuggester::new(&config.suggest)?.suggest(&files, message) else {
+        println!("{} No rule matched these changes. Pick a context from 'jogen context list'.", "⚠".yellow());
+        return Ok(());
+    };
+
+    println!("\nSuggested context: {}", suggestion.context.as_str().yellow().bold());
+    let source = if suggestion.configured { " (rule from config.toml)" } else { "" };
+    println!("Reason:            {}{}", suggestion.reason, source.dimmed());
+    if let Some(evidence) = &suggestion.evidence {
+        println!("                   {}", evidence.dimmed());
+    }
+    println!(
+        "\nRun: jogen snapshot -c {} -m {:?}",
+        suggestion.context.as_str(),
+        message.unwrap_or("<message>")
+    );
+
+    Ok(())
+}
+
 pub fn status() -> Result<()> {
     let repo = JogenRepo::from_cwd()?;
 
@@ -85,13 +179,54 @@ pub fn status() -> Result<()> {
         println!("Last Snapshot: {}", hash[..7].cyan());
     }
 
-    if let Ok(Some(status)) = repo.ref_store.get_integration_status() {
-        println!("\n{} {}", "Status:".red().bold(), "INTEGRATING".red());
+    let paused = repo.ref_store.get_integration_status()?;
+    if let Some(status) = &paused {
+        let (state, label) = match status.operation {
+            IntegrationOperation::Integrate => ("INTEGRATING", "Integrating target"),
+            IntegrationOperation::Port => ("PORTING", "Porting snapshot"),
+            IntegrationOperation::Revert => ("REVERTING", "Reverting snapshot"),
+            IntegrationOperation::Checkout => ("CHECKING OUT", "Merging local changes onto"),
+        };
+        println!("\n{} {}", "Status:".red().bold(), state.red());
         println!(
-            "Integrating target: {} ({})",
+            "{}: {} ({})",
+            label,
             status.target_name.yellow(),
             status.target_hash[..7].cyan()
         );
+
+        let unresolved: Vec<&String> = status
+            .conflict_paths
+            .iter()
+            .filter(|path| repo.root_path.join(path).exists())
+            .collect();
+        if !unresolved.is_empty() {
+            println!("Unresolved conflict markers:");
+            for path in unresolved {
+                println!("  - {}", path.red());
+            }
+        }
+        println!(
+            "  (Use 'jogen {} --continue' once resolved, or 'jogen {} --abort' to cancel)",
+            status.operation.as_str(),
+            status.operation.as_str()
+        );
+
+        if !status.reused_resolutions.is_empty() {
+            println!("Resolved using recorded resolutions:");
+            for path in &status.reused_resolutions {
+                println!("  - {}", path.green());
+            }
+        }
+    }
+
+    if let Some(state) = repo.ref_store.get_bisect_state()? {
+        let origin = state
+            .origin_track
+            .unwrap_or_else(|| state.origin_hash[..7].to_string());
+        println!("\n{} {}", "Status:".red().bold(), "BISECTING".red());
+        println!("Started from: {} ({} verdicts so far)", origin.yellow(), state.verdicts.len());
+        println!("  (Use 'jogen bisect good|bad|skip' to continue, or 'jogen bisect reset' to stop)");
     }
 
     // Check for changes
@@ -112,7 +247,9 @@ pub fn status() -> Result<()> {
                 println!("{}", "Workspace is clean.".green());
             } else {
                 println!("{}", "Uncommitted changes present.".yellow().bold());
-                println!("  (Use 'jogen snapshot' to record your work)");
+                if paused.is_none() {
+                    println!("  (Use 'jogen snapshot' to record your work, or 'jogen shelve push' to set it aside)");
+                }
             }
         }
         (None, Some(_)) => {
@@ -124,24 +261,74 @@ pub fn status() -> Result<()> {
         }
     }
 
+    let shelves = repo.ref_store.list_shelves()?.len();
+    if shelves > 0 {
+        println!("Shelves: {} (see 'jogen shelve list')", shelves);
+    }
+
     Ok(())
 }
 
-pub fn checkout(target: String) -> Result<()> {
+pub fn checkout(args: CheckoutArgs) -> Result<()> {
     let repo = JogenRepo::from_cwd()?;
-    let hydrator = jogen_core::hydrator::Hydrator::new(&repo.object_store);
+    let hydrator = Hydrator::new(&repo.object_store);
+
+    // --- HANDLE ABORT ---
+    if args.abort {
+        let Some(status) = repo.ref_store.get_integration_status()? else {
+            println!("{} Nothing to abort: no checkout in progress.", "ℹ".blue());
+            return Ok(());
+        };
+
+        println!("{} Aborting {}...", "⚠".yellow(), status.operation.as_str());
+        abort_paused_operation(&repo, &status)?;
+
+        println!("{} Aborted {}. Workspace restored.", "✔".green(), status.operation.as_str());
+        return Ok(());
+    }
+
+    // --- HANDLE CONTINUE ---
+    if args.r#continue {
+        let status = repo
+            .ref_store
+            .get_integration_status()?
+            .ok_or_else(|| anyhow::anyhow!("No checkout in progress."))?;
+
+        if status.operation != IntegrationOperation::Checkout {
+            return Err(anyhow::anyhow!(
+                "A {} is in progress. Use 'jogen {} --continue' instead.",
+                status.operation.as_str(),
+                status.operation.as_str()
+            ));
+        }
+
+        // The resolved files stay as uncommitted changes on top of the checked-out snapshot.
+        resolved_tree(&repo, &status)?;
+        repo.ref_store.clear_integration()?;
+
+        println!("{} Checkout complete. Your merged changes are uncommitted.", "✔".green());
+        return Ok(());
+    }
+
+    // --- HANDLE NEW CHECKOUT ---
+    if let Some(status) = repo.ref_store.get_integration_status()? {
+        return Err(anyhow::anyhow!(
+            "A {} is in progress. Use 'jogen {} --continue' or '--abort' first.",
+            status.operation.as_str(),
+            status.operation.as_str()
+        ));
+    }
+
+    let target = args
+        .target
+        .ok_or_else(|| anyhow::anyhow!("Must provide a track, label or snapshot to check out."))?;
 
     // 1. Resolve target to a snapshot hash and determine if it's a track switch
     let (target_snapshot_hash, target_track) = if let Some(hash) = repo.ref_store.resolve_track(&target)? {
-        (Some(hash), Some(target.clone()))
+        (hash, Some(target.clone()))
     } else {
-        // If it's not a track, assume it's a hash
-        (Some(target.clone()), None)
-    };
-
-    let target_snapshot_hash = match target_snapshot_hash {
-        Some(hash) => hash,
-        None => return Err(anyhow::anyhow!("Could not resolve target {}", target)),
+        // If it's not a track, resolve it as any other revision (label, hash or prefix)
+        (repo.resolve_revision(&target)?, None)
     };
 
     println!(
@@ -154,11 +341,12 @@ pub fn checkout(target: String) -> Result<()> {
         }
     );
 
-    // 2. Safety check: Are there uncommitted changes?
+    // 2. Read the current state: HEAD and the workspace, which may have uncommitted changes
     let current_snapshot_hash = repo.ref_store.read_head()?;
+    let current_track = repo.ref_store.current_track()?;
 
-    let head_tree_hash = if let Some(hash) = current_snapshot_hash {
-        let (_, content) = repo.object_store.read_object(&hash)?;
+    let head_tree_hash = if let Some(hash) = &current_snapshot_hash {
+        let (_, content) = repo.object_store.read_object(hash)?;
         let snapshot_data = Snapshot::deserialize(&content)?;
         Some(snapshot_data.directory_hash)
     } else {
@@ -168,14 +356,6 @@ pub fn checkout(target: String) -> Result<()> {
     let indexer = Indexer::new(&repo.object_store, &repo.root_path);
     let workspace_tree_hash = indexer.index_path(&repo.root_path)?;
 
-    if let (Some(head_tree), Some(workspace_tree)) = (head_tree_hash.as_ref(), workspace_tree_hash.as_ref()) {
-        if head_tree != workspace_tree {
-            return Err(anyhow::anyhow!(
-                "Uncommitted changes found in workspace.\nPlease snapshot or discard changes before checking out."
-            ));
-        }
-    }
-
     // 3. Resolve target tree
     if !repo.object_store.exists(&target_snapshot_hash) {
         return Err(anyhow::anyhow!(
@@ -197,18 +377,79 @@ pub fn checkout(target: String) -> Result<()> {
     };
 
     // 4. Apply changes
-    if let Some(head_tree) = head_tree_hash.as_ref() {
-        hydrator.apply_diff(head_tree, &target_tree_hash, &repo.root_path)?;
-    } else {
-        // Initial checkout (empty workspace)
-        hydrator.hydrate_directory(&target_tree_hash, &repo.root_path)?;
+    let mut conflicts = Vec::new();
+    let mut origin = None;
+    match (head_tree_hash.as_deref(), workspace_tree_hash.as_deref()) {
+        (Some(head_tree), Some(workspace_tree)) if head_tree != workspace_tree => {
+            // Local changes are carried over. Without --merge, only paths the target leaves
+            // alone may have them.
+            let reader = TreeReader::new(&repo.object_store);
+            if !args.merge {
+                let local = reader.diff(Some(head_tree), Some(workspace_tree))?;
+                let incoming = reader.diff(Some(head_tree), Some(&target_tree_hash))?;
+                let overlapping = overlapping_paths(&local, &incoming);
+                if !overlapping.is_empty() {
+                    println!("\nYour uncommitted changes to these paths would be overwritten:");
+                    for path in &overlapping {
+                        println!("  - {}", path.red());
+                    }
+                    return Err(anyhow::anyhow!(
+                        "Checkout would overwrite local changes.\nPlease snapshot them, shelve them with 'jogen shelve push', or check out with --merge."
+                    ));
+                }
+            }
+
+            let merge_engine = jogen_core::merge::MergeEngine::new(&repo.object_store);
+            let merge_result =
+                merge_engine.merge_trees(Some(head_tree), Some(workspace_tree), Some(&target_tree_hash), "")?;
+            let merged_tree_hash = merge_result
+                .tree_hash
+                .ok_or_else(|| anyhow::anyhow!("Merge failed to produce a tree hash."))?;
+
+            hydrator.apply_diff(workspace_tree, &merged_tree_hash, &repo.root_path)?;
+
+            let carried = reader.diff(Some(&target_tree_hash), Some(&merged_tree_hash))?;
+            print_preview_changes("Local changes carried over:", &carried);
+            conflicts = merge_result.conflicts;
+            origin = current_snapshot_hash.map(|head_hash| CheckoutOrigin {
+                track: current_track,
+                head_hash,
+                workspace_tree: workspace_tree.to_string(),
+            });
+        }
+        (Some(head_tree), _) => {
+            hydrator.apply_diff(head_tree, &target_tree_hash, &repo.root_path)?;
+        }
+        (None, _) => {
+            // Initial checkout (empty workspace)
+            hydrator.hydrate_directory(&target_tree_hash, &repo.root_path)?;
+        }
     }
 
     // 5. Update HEAD
     if let Some(track_name) = target_track {
         repo.ref_store.set_head_to_track(&track_name)?;
     } else {
-        repo.ref_store.update_head(&target_snapshot_hash)?;
+        repo.ref_store.detach_head(&target_snapshot_hash)?;
+    }
+
+    if !conflicts.is_empty() {
+        println!("\n{} Conflicts found! Pausing checkout.", "⚠".yellow().bold());
+
+        pause_on_conflicts(
+            &repo,
+            &hydrator,
+            conflicts,
+            head_tree_hash.as_deref().unwrap_or(""),
+            &target_snapshot_hash,
+            &target,
+            IntegrationOperation::Checkout,
+        )?;
+        if let Some(origin) = &origin {
+            repo.ref_store.record_checkout_origin(origin)?;
+        }
+
+        return Err(anyhow::anyhow!("Checkout paused due to conflicts."));
     }
 
     println!("{} Checkout complete", "✔".green());
@@ -216,9 +457,97 @@ pub fn checkout(target: String) -> Result<()> {
     Ok(())
 }
 
+/// Paths changed both locally and by the target, in different ways. A directory overlaps with
+/// the paths under it.
+fn overlapping_paths(local: &[TreeChange], incoming: &[TreeChange]) -> Vec<String> {
+    let nested = |outer: &str, inner: &str| inner.starts_with(&format!("{}/", outer));
+
+    local
+        .iter()
+        .filter(|change| {
+            incoming.iter().any(|other| {
+                if change.path == other.path {
+                    change.new != other.new
+                } else {
+                    nested(&change.path, &other.path) || nested(&other.path, &change.path)
+                }
+            })
+        })
+        .map(|change| change.path.clone())
+        .collect()
+}
+
+/// Rewrites the given files and directories from a snapshot, HEAD by default, without moving HEAD.
+pub fn restore(args: RestoreArgs) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+    let hydrator = Hydrator::new(&repo.object_store);
+    let reader = TreeReader::new(&repo.object_store);
+
+    let source = args.source.as_deref().unwrap_or("HEAD");
+    let source_hash = repo.resolve_revision(source)?;
+    let source_tree = repo.load_snapshot(&source_hash)?.directory_hash;
+
+    let mut paths: Vec<String> = Vec::new();
+    for path in &args.paths {
+        paths.push(repo.relative_path(path)?);
+    }
+    // Paths inside another restored path are restored with it. The project root contains all.
+    let contains = |outer: &str, inner: &str| {
+        outer.is_empty() || inner == outer || inner.starts_with(&format!("{}/", outer))
+    };
+    paths.sort();
+    paths.dedup();
+    let paths: Vec<String> = paths
+        .iter()
+        .filter(|inner| !paths.iter().any(|outer| outer != *inner && contains(outer, inner)))
+        .cloned()
+        .collect();
+
+    let indexer = Indexer::new(&repo.object_store, &repo.root_path);
+    let workspace_tree = indexer.index_path(&repo.root_path)?;
+
+    // Check every path before touching any file.
+    for path in &paths {
+        if path.is_empty() {
+            continue;
+        }
+        let in_workspace = match &workspace_tree {
+            Some(tree) => reader.lookup(tree, path)?.is_some(),
+            None => false,
+        };
+        if !in_workspace && reader.lookup(&source_tree, path)?.is_none() {
+            return Err(anyhow::anyhow!(
+                "Path '{}' is neither in the workspace nor in {}.",
+                path,
+                &source_hash[..7]
+            ));
+        }
+    }
+
+    let changes: Vec<TreeChange> = reader
+        .diff(workspace_tree.as_deref(), Some(&source_tree))?
+        .into_iter()
+        .filter(|change| paths.iter().any(|path| contains(path, &change.path)))
+        .collect();
+
+    if changes.is_empty() {
+        println!("{} Nothing to restore, the paths already match {}.", "ℹ".blue(), &source_hash[..7]);
+        return Ok(());
+    }
+
+    for path in &paths {
+        hydrator.restore_path(workspace_tree.as_deref(), &source_tree, path, &repo.root_path)?;
+    }
+
+    print_preview_changes(&format!("Restored from {}:", &source_hash[..7]), &changes);
+    println!("\n{} Restored {} file(s)", "✔".green(), changes.len());
+
+    Ok(())
+}
+
 pub fn integrate(args: IntegrateArgs) -> Result<()> {
     let repo = JogenRepo::from_cwd()?;
-    let hydrator = jogen_core::hydrator::Hydrator::new(&repo.object_store);
+    let hydrator = Hydrator::new(&repo.object_store);
 
     // --- HANDLE ABORT ---
     if args.abort {
@@ -229,30 +558,11 @@ pub fn integrate(args: IntegrateArgs) -> Result<()> {
         }
 
         let integration_status = integration_status.unwrap();
-        println!("{} Aborting integration...", "⚠".yellow());
+        println!("{} Aborting {}...", "⚠".yellow(), integration_status.operation.as_str());
 
-        // Delete only integration conflict markers tracked for this integration.
-        for conflict_path in &integration_status.conflict_paths {
-            let absolute = repo.root_path.join(conflict_path);
-            if absolute.exists() {
-                std::fs::remove_file(absolute)?;
-            }
-        }
-
-        // Restore HEAD state to wipe partial hydration and remove stale files.
-        if let Some(head_hash) = repo.ref_store.read_head()? {
-            let (_, content) = repo.object_store.read_object(&head_hash)?;
-            let snapshot = Snapshot::deserialize(&content)?;
-            let indexer = Indexer::new(&repo.object_store, &repo.root_path);
-            if let Some(current_tree_hash) = indexer.index_path(&repo.root_path)? {
-                hydrator.apply_diff(&current_tree_hash, &snapshot.directory_hash, &repo.root_path)?;
-            } else {
-                hydrator.hydrate_directory(&snapshot.directory_hash, &repo.root_path)?;
-            }
-        }
-        repo.ref_store.clear_integration()?;
+        abort_paused_operation(&repo, &integration_status)?;
 
-        println!("{} Integration aborted. Workspace restored.", "✔".green());
+        println!("{} Aborted {}. Workspace restored.", "✔".green(), integration_status.operation.as_str());
         return Ok(());
     }
 
@@ -265,28 +575,21 @@ pub fn integrate(args: IntegrateArgs) -> Result<()> {
         let integration_status = repo.ref_store.get_integration_status()?
             .ok_or_else(|| anyhow::anyhow!("No integration in progress."))?;
 
-        // Check only the conflict markers recorded for this integration.
-        let mut conflicts_remain = false;
-        for conflict_path in &integration_status.conflict_paths {
-            let absolute = repo.root_path.join(conflict_path);
-            if absolute.exists() {
-                println!("  - {}", conflict_path.red());
-                conflicts_remain = true;
-            }
+        if integration_status.operation != IntegrationOperation::Integrate {
+            return Err(anyhow::anyhow!(
+                "A {} is in progress. Use 'jogen {} --continue' instead.",
+                integration_status.operation.as_str(),
+                integration_status.operation.as_str()
+            ));
         }
 
-        if conflicts_remain {
-            return Err(anyhow::anyhow!("Cannot continue. Unresolved '.incoming' files still exist."));
-        }
+        let resolved_tree_hash = resolved_tree(&repo, &integration_status)?;
 
         println!("{} Finalizing integration...", "⚙".blue());
 
-        // Snapshot the resolved state
-        let indexer = Indexer::new(&repo.object_store, &repo.root_path);
-        let resolved_tree_hash = indexer.index_path(&repo.root_path)?.ok_or_else(|| anyhow::anyhow!("Workspace is empty"))?;
-
         let parent_hashes = vec![head_hash, integration_status.target_hash];
-        let message = format!("Merge track '{}' into '{}'", integration_status.target_name, current_track);
+        let target_kind = if repo.ref_store.resolve_track(&integration_status.target_name)?.is_some() { "track " } else { "" };
+        let message = format!("Merge {}'{}' into '{}'", target_kind, integration_status.target_name, current_track);
         
         let snapshot = Snapshot::new(
             resolved_tree_hash,
@@ -297,7 +600,7 @@ pub fn integrate(args: IntegrateArgs) -> Result<()> {
             message,
         );
 
-        let snapshot_hash = repo.object_store.write_object(snapshot.serialize()?.as_ref(), ObjectType::Snapshot)?;
+        let snapshot_hash = repo.write_snapshot(&snapshot)?;
         repo.ref_store.update_head(&snapshot_hash)?;
         repo.ref_store.clear_integration()?;
 
@@ -310,19 +613,10 @@ pub fn integrate(args: IntegrateArgs) -> Result<()> {
         return Err(anyhow::anyhow!("An integration is already in progress. Use --continue or --abort."));
     }
 
-    let target = args.target.ok_or_else(|| anyhow::anyhow!("Must provide a target to integrate."))?;
-    let target_hash = repo.ref_store.resolve_track(&target)?.ok_or_else(|| anyhow::anyhow!("Could not resolve target track: {}", target))?;
-
-    if head_hash == target_hash {
-        println!("{} Already up to date.", "✔".green());
-        return Ok(());
+    if args.targets.is_empty() {
+        return Err(anyhow::anyhow!("Must provide a target to integrate."));
     }
 
-    println!("{} Integrating {} into {}...", "⚙".blue(), target.yellow(), current_track.yellow());
-
-    let graph = jogen_core::graph::GraphTraversal::new(&repo.object_store);
-    let base_hash = graph.find_common_ancestor(&head_hash, &target_hash)?;
-
     let get_tree = |hash: &str| -> Result<String> {
         let (kind, content) = repo.object_store.read_object(hash)?;
         if kind == ObjectType::Snapshot {
@@ -333,15 +627,88 @@ pub fn integrate(args: IntegrateArgs) -> Result<()> {
         }
     };
 
-    let base_tree = match base_hash {
-        Some(ref h) => Some(get_tree(h)?),
-        None => None,
-    };
+    let graph = repo.graph()?;
+
+    // Skip targets that are already part of HEAD's history.
+    let mut resolved: Vec<(String, String)> = Vec::new();
+    for target in &args.targets {
+        let target_hash = repo.resolve_revision(target)?;
+        if head_hash == target_hash || graph.is_ancestor(&target_hash, &head_hash)? {
+            if args.targets.len() > 1 {
+                println!("{} {} is already integrated.", "ℹ".blue(), target.yellow());
+            }
+            continue;
+        }
+        if !resolved.iter().any(|(_, hash)| hash == &target_hash) {
+            resolved.push((target.clone(), target_hash));
+        }
+    }
+
+    // A target contained in another target's history adds nothing on its own.
+    let mut independent = Vec::new();
+    for (target, target_hash) in &resolved {
+        let mut contained = false;
+        for (_, other_hash) in &resolved {
+            if other_hash != target_hash && graph.is_ancestor(target_hash, other_hash)? {
+                contained = true;
+                break;
+            }
+        }
+        if !contained {
+            independent.push((target.clone(), target_hash.clone()));
+        }
+    }
+
+    if independent.is_empty() {
+        println!("{} Already up to date.", "✔".green());
+        return Ok(());
+    }
+
     let head_tree = get_tree(&head_hash)?;
-    let target_tree = get_tree(&target_hash)?;
+    if args.dry_run {
+        return preview_integration(&repo, &graph, &current_track, &head_hash, &head_tree, &independent, &args);
+    }
+
+    ensure_clean_workspace(&repo, &head_tree, "integrating")?;
+
+    if independent.len() > 1 {
+        if args.ff_only {
+            return Err(anyhow::anyhow!(
+                "Cannot fast-forward '{}' to several targets at once.",
+                current_track
+            ));
+        }
+        return integrate_octopus(&repo, &graph, &current_track, &head_hash, &head_tree, &independent);
+    }
+
+    let (target, target_hash) = independent.remove(0);
+    let target_kind = if repo.ref_store.resolve_track(&target)?.is_some() { "track " } else { "" };
+
+    // --- FAST-FORWARD ---
+    let can_fast_forward = graph.is_ancestor(&head_hash, &target_hash)?;
+    if args.ff_only && !can_fast_forward {
+        return Err(anyhow::anyhow!(
+            "Cannot fast-forward '{}' to {}: the histories have diverged.",
+            current_track,
+            target
+        ));
+    }
+
+    if can_fast_forward && !args.no_ff {
+        println!("{} Fast-forwarding {} to {}...", "⚙".blue(), current_track.yellow(), target.yellow());
+        hydrator.apply_diff(&head_tree, &get_tree(&target_hash)?, &repo.root_path)?;
+        repo.ref_store.update_head(&target_hash)?;
+        println!("{} Integration complete. {} now at {}", "✔".green(), current_track.yellow(), target_hash[..7].yellow());
+        return Ok(());
+    }
+
+    println!("{} Integrating {} into {}...", "⚙".blue(), target.yellow(), current_track.yellow());
 
     let merge_engine = jogen_core::merge::MergeEngine::new(&repo.object_store);
-    let merge_result = merge_engine.merge_trees(base_tree.as_deref(), Some(&head_tree), Some(&target_tree), "");
+    let base_tree = merge_engine.merge_base_tree(&graph, &head_hash, &target_hash)?;
+    let target_tree = get_tree(&target_hash)?;
+
+    let merge_result = merge_engine.merge_trees(base_tree.as_deref(), Some(&head_tree), Some(&target_tree), "")?;
     let merged_tree_hash = merge_result
         .tree_hash
         .as_ref()
@@ -351,30 +718,22 @@ pub fn integrate(args: IntegrateArgs) -> Result<()> {
     if !merge_result.conflicts.is_empty() {
         println!("{} Conflicts found! Pausing integration.", "⚠".yellow().bold());
         
-        let conflict_paths = hydrator.write_conflict_files(&merge_result.conflicts, &repo.root_path)?;
-        repo.ref_store.begin_integration(
-            base_hash.as_deref().unwrap_or(""),
+        pause_on_conflicts(
+            &repo,
+            &hydrator,
+            merge_result.conflicts,
+            base_tree.as_deref().unwrap_or(""),
             &target_hash,
             &target,
-            &conflict_paths,
+            IntegrationOperation::Integrate,
         )?;
 
-        println!("\nThe following files have conflicts. Incoming versions have been saved alongside your files:");
-        for conflict in &merge_result.conflicts {
-            println!("  - {}", conflict.path.red());
-        }
-        println!("\nTo resolve:");
-        println!("  1. Run 'jogen diff <file>' to semantically compare changes.");
-        println!("  2. Edit your file to the desired final state.");
-        println!("  3. Delete the .incoming file.");
-        println!("  4. Run 'jogen integrate --continue'.");
-        
         return Err(anyhow::anyhow!("Integration paused due to conflicts."));
     }
 
     // No conflicts, auto-commit
     let parent_hashes = vec![head_hash, target_hash];
-    let message = format!("Merge track '{}' into '{}'", target, current_track);
+    let message = format!("Merge {}'{}' into '{}'", target_kind, target, current_track);
     let snapshot = Snapshot::new(
         merged_tree_hash.to_string(),
         parent_hashes,
@@ -384,13 +743,384 @@ pub fn integrate(args: IntegrateArgs) -> Result<()> {
         message,
     );
 
-    let snapshot_hash = repo.object_store.write_object(snapshot.serialize()?.as_ref(), ObjectType::Snapshot)?;
+    let snapshot_hash = repo.write_snapshot(&snapshot)?;
     repo.ref_store.update_head(&snapshot_hash)?;
 
     println!("{} Integration complete. Created merge snapshot {}", "✔".green(), snapshot_hash[..7].yellow());
     Ok(())
 }
 
+/// Integrates several targets at once, recording a single merge snapshot with all of them as parents.
+/// Each target is merged in memory in turn; if any of them conflicts, nothing is written and the
+/// offending target is reported.
+fn integrate_octopus(
+    repo: &JogenRepo,
+    graph: &GraphTraversal,
+    current_track: &str,
+    head_hash: &str,
+    head_tree: &str,
+    targets: &[(String, String)],
+) -> Result<()> {
+    let names: Vec<String> = targets.iter().map(|(name, _)| format!("'{}'", name)).collect();
+    println!("{} Integrating {} into {}...", "⚙".blue(), names.join(", ").yellow(), current_track.yellow());
+
+    let merge_engine = jogen_core::merge::MergeEngine::new(&repo.object_store);
+    let tree_reader = TreeReader::new(&repo.object_store);
+    let mut merged_tree = head_tree.to_string();
+    // The earlier target whose merge last changed each path. Other paths are as in HEAD.
+    let mut changed_by: HashMap<String, &str> = HashMap::new();
+    // HEAD and the targets merged so far: each target is merged against their merge bases.
+    let mut merged: Vec<&str> = vec![head_hash];
+
+    for (target, target_hash) in targets {
+        let (_, content) = repo.object_store.read_object(target_hash)?;
+        let target_tree = Snapshot::deserialize(&content)?.directory_hash;
+        let base_tree = merge_engine.combined_merge_base_tree(graph, &merged, target_hash)?;
+
+        let merge_result = merge_engine.merge_trees(base_tree.as_deref(), Some(&merged_tree), Some(&target_tree), "")?;
+
+        if !merge_result.conflicts.is_empty() {
+            println!("{} '{}' conflicts:", "⚠".yellow().bold(), target.yellow());
+            for conflict in &merge_result.conflicts {
+                // A conflicting directory can hold files changed by several sides.
+                let mut sides: Vec<String> = changed_by
+                    .iter()
+                    .filter(|(path, _)| {
+                        **path == conflict.path
+                            || path.starts_with(&format!("{}/", conflict.path))
+                            || conflict.path.starts_with(&format!("{}/", path))
+                    })
+                    .map(|(_, side)| format!("'{}'", side))
+                    .collect();
+                sides.sort();
+                sides.dedup();
+                let side = if sides.is_empty() { "HEAD".to_string() } else { sides.join(", ") };
+
+                println!(
+                    "  - {} {}",
+                    conflict.path.red(),
+                    format!("({} with {})", conflict.kind, side).dimmed()
+                );
+            }
+            println!("\nIntegrate '{}' on its own to resolve these conflicts.", target);
+            return Err(anyhow::anyhow!("Integration stopped: '{}' introduced conflicts. Workspace unchanged.", target));
+        }
+
+        let next_tree = merge_result
+            .tree_hash
+            .ok_or_else(|| anyhow::anyhow!("Merge failed to produce a tree hash."))?;
+        for change in tree_reader.diff(Some(&merged_tree), Some(&next_tree))? {
+            changed_by.insert(change.path, target);
+        }
+        merged_tree = next_tree;
+        merged.push(target_hash);
+    }
+
+    let hydrator = Hydrator::new(&repo.object_store);
+    hydrator.apply_diff(head_tree, &merged_tree, &repo.root_path)?;
+
+    let mut parent_hashes = vec![head_hash.to_string()];
+    parent_hashes.extend(targets.iter().map(|(_, hash)| hash.clone()));
+
+    let message = format!("Merge {} into '{}'", names.join(", "), current_track);
+    let snapshot = Snapshot::new(
+        merged_tree,
+        parent_hashes,
+        "Jogen User <user@jogen.com>".to_string(),
+        chrono::Utc::now().timestamp(),
+        jogen_core::objects::snapshot::SnapshotContext::Merge,
+        message,
+    );
+
+    let snapshot_hash = repo.write_snapshot(&snapshot)?;
+    repo.ref_store.update_head(&snapshot_hash)?;
+
+    println!(
+        "{} Integration complete. Created merge snapshot {} with {} parents",
+        "✔".green(),
+        snapshot_hash[..7].yellow(),
+        targets.len() + 1
+    );
+    Ok(())
+}
+
+/// Reports what integrating `targets` would do: the files that would change, those merged
+/// automatically from edits on both sides, and those that would conflict. The merge only
+/// happens in memory; the workspace and refs are left untouched.
+fn preview_integration(
+    repo: &JogenRepo,
+    graph: &GraphTraversal,
+    current_track: &str,
+    head_hash: &str,
+    head_tree: &str,
+    targets: &[(String, String)],
+    args: &IntegrateArgs,
+) -> Result<()> {
+    let merge_engine = jogen_core::merge::MergeEngine::new(&repo.object_store);
+    let tree_reader = TreeReader::new(&repo.object_store);
+
+    let target_tree = |hash: &str| -> Result<String> {
+        let (_, content) = repo.object_store.read_object(hash)?;
+        Ok(Snapshot::deserialize(&content)?.directory_hash)
+    };
+
+    if let [(target, target_hash)] = targets {
+        let can_fast_forward = graph.is_ancestor(head_hash, target_hash)?;
+        if args.ff_only && !can_fast_forward {
+            return Err(anyhow::anyhow!(
+                "Cannot fast-forward '{}' to {}: the histories have diverged.",
+                current_track,
+                target
+            ));
+        }
+
+        if can_fast_forward && !args.no_ff {
+            println!("{} Would fast-forward {} to {}.", "ℹ".blue(), current_track.yellow(), target.yellow());
+            let changes = tree_reader.diff(Some(head_tree), Some(&target_tree(target_hash)?))?;
+            print_preview_changes("Would update:", &changes);
+            return Ok(());
+        }
+    } else if args.ff_only {
+        return Err(anyhow::anyhow!("Cannot fast-forward '{}' to several targets at once.", current_track));
+    }
+
+    let names: Vec<String> = targets.iter().map(|(name, _)| format!("'{}'", name)).collect();
+    println!("{} Previewing integration of {} into {}...", "⚙".blue(), names.join(", ").yellow(), current_track.yellow());
+
+    let mut merged_tree = head_tree.to_string();
+    let mut merged: Vec<&str> = vec![head_hash];
+    let mut conflicts = Vec::new();
+    let mut both_changed = HashSet::new();
+
+    for (target, target_hash) in targets {
+        let base_tree = merge_engine.combined_merge_base_tree(graph, &merged, target_hash)?;
+        let incoming_tree = target_tree(target_hash)?;
+
+        // Paths edited on both sides are the ones a clean result had to merge.
+        let ours: HashSet<String> = tree_reader
+            .diff(base_tree.as_deref(), Some(&merged_tree))?
+            .into_iter()
+            .map(|change| change.path)
+            .collect();
+        for change in tree_reader.diff(base_tree.as_deref(), Some(&incoming_tree))? {
+            if ours.contains(&change.path) {
+                both_changed.insert(change.path);
+            }
+        }
+
+        let merge_result = merge_engine.merge_trees(base_tree.as_deref(), Some(&merged_tree), Some(&incoming_tree), "")?;
+        conflicts.extend(merge_result.conflicts.into_iter().map(|conflict| (target.clone(), conflict)));
+        merged_tree = merge_result
+            .tree_hash
+            .ok_or_else(|| anyhow::anyhow!("Merge failed to produce a tree hash."))?;
+        merged.push(target_hash);
+    }
+
+    let conflicted: HashSet<&str> = conflicts.iter().map(|(_, conflict)| conflict.path.as_str()).collect();
+    let is_conflicted = |path: &str| {
+        conflicted
+            .iter()
+            .any(|c| path == *c || path.strip_prefix(*c).is_some_and(|rest| rest.starts_with('/')))
+    };
+
+    let (auto_merged, updated): (Vec<TreeChange>, Vec<TreeChange>) = tree_reader
+        .diff(Some(head_tree), Some(&merged_tree))?
+        .into_iter()
+        .filter(|change| !is_conflicted(&change.path))
+        .partition(|change| both_changed.contains(&change.path));
+
+    print_preview_changes("Would update:", &updated);
+    print_preview_changes("Would auto-merge:", &auto_merged);
+
+    if conflicts.is_empty() {
+        println!("\n{} The integration would complete cleanly.", "✔".green());
+        return Ok(());
+    }
+
+    println!("\n{}", "Would conflict:".red().bold());
+    for (target, conflict) in &conflicts {
+        let origin = if targets.len() > 1 { format!(" from '{}'", target) } else { String::new() };
+        println!("  - {} {}", conflict.path.red(), format!("({}{})", conflict.kind, origin).dimmed());
+    }
+
+    Err(anyhow::anyhow!("The integration would conflict in {} path(s).", conflicts.len()))
+}
+
+pub(super) fn print_preview_changes(title: &str, changes: &[TreeChange]) {
+    if changes.is_empty() {
+        return;
+    }
+    println!("\n{}", title.bold());
+    for change in changes {
+        let code = change.kind.as_char().to_string();
+        let code = match change.kind {
+            ChangeKind::Added => code.green(),
+            ChangeKind::Deleted => code.red(),
+            ChangeKind::Modified => code.yellow(),
+        };
+        println!("  {} {}", code, change.path);
+    }
+}
+
+/// Fails if the workspace differs from the given HEAD tree.
+pub(super) fn ensure_clean_workspace(repo: &JogenRepo, head_tree: &str, action: &str) -> Result<()> {
+    let indexer = Indexer::new(&repo.object_store, &repo.root_path);
+    if indexer.index_path(&repo.root_path)?.as_deref() != Some(head_tree) {
+        return Err(anyhow::anyhow!(
+            "Uncommitted changes found in workspace.\nPlease snapshot them, or discard them with 'jogen restore .', before {}.",
+            action
+        ));
+    }
+    Ok(())
+}
+
+/// Pauses an operation on conflicts: reuses recorded resolutions where possible, writes the
+/// `.incoming` files for the rest, saves the paused state and explains how to resume.
+pub(super) fn pause_on_conflicts(
+    repo: &JogenRepo,
+    hydrator: &Hydrator,
+    conflicts: Vec<MergeConflict>,
+    base_hash: &str,
+    target_hash: &str,
+    target_name: &str,
+    operation: IntegrationOperation,
+) -> Result<()> {
+    let cache = ResolutionCache::new(&repo.object_store, &repo.root_path);
+    let mut remaining = Vec::new();
+    let mut recorded_conflicts = Vec::new();
+    let mut reused_resolutions = Vec::new();
+
+    for conflict in conflicts {
+        let Some(preimage) = cache.preimage(&conflict)? else {
+            remaining.push(conflict);
+            continue;
+        };
+
+        cache.remember(&preimage)?;
+        recorded_conflicts.push((preimage.key.clone(), conflict.path.clone()));
+
+        match cache.resolve(&preimage)? {
+            Some(resolved) => {
+                std::fs::write(repo.root_path.join(&conflict.path), resolved)?;
+                reused_resolutions.push(conflict.path);
+            }
+            None => remaining.push(conflict),
+        }
+    }
+
+    let conflict_paths = hydrator.write_conflict_files(&remaining, &repo.root_path)?;
+    repo.ref_store.begin_integration(base_hash, target_hash, target_name, &conflict_paths, operation)?;
+    repo.ref_store.record_resolutions(&recorded_conflicts, &reused_resolutions)?;
+
+    if !reused_resolutions.is_empty() {
+        println!("\nThe following files were resolved using recorded resolutions. Review them before continuing:");
+        for path in &reused_resolutions {
+            println!("  - {}", path.green());
+        }
+    }
+
+    if remaining.is_empty() {
+        println!("\nRun 'jogen {} --continue' once you are happy with the result.", operation.as_str());
+    } else {
+        print_conflict_help(&remaining, operation.as_str());
+    }
+
+    Ok(())
+}
+
+/// Lists conflicted paths and explains how to resolve them and resume `command`.
+fn print_conflict_help(conflicts: &[MergeConflict], command: &str) {
+    println!("\nThe following files have conflicts. Incoming versions have been saved alongside your files:");
+    for conflict in conflicts {
+        println!("  - {} {}", conflict.path.red(), format!("({})", conflict.kind).dimmed());
+    }
+    println!("\nTo resolve:");
+    println!("  1. Run 'jogen diff <file>' to semantically compare changes.");
+    println!("  2. Edit your file to the desired final state.");
+    println!("  3. Delete the .incoming file.");
+    println!("  4. Run 'jogen {} --continue'.", command);
+}
+
+/// Removes the conflict markers of a paused operation, restores the workspace to HEAD
+/// and clears the paused state.
+pub(super) fn abort_paused_operation(repo: &JogenRepo, status: &IntegrationStatus) -> Result<()> {
+    let hydrator = Hydrator::new(&repo.object_store);
+
+    // Delete only conflict markers tracked for this operation.
+    for conflict_path in &status.conflict_paths {
+        let absolute = repo.root_path.join(conflict_path);
+        if absolute.is_dir() {
+            std::fs::remove_dir_all(absolute)?;
+        } else if absolute.exists() {
+            std::fs::remove_file(absolute)?;
+        }
+    }
+
+    // A checkout goes back to where it started, uncommitted changes included.
+    if let Some(origin) = &status.checkout_origin {
+        let indexer = Indexer::new(&repo.object_store, &repo.root_path);
+        match indexer.index_path(&repo.root_path)? {
+            Some(current_tree_hash) => {
+                hydrator.apply_diff(&current_tree_hash, &origin.workspace_tree, &repo.root_path)?
+            }
+            None => hydrator.hydrate_directory(&origin.workspace_tree, &repo.root_path)?,
+        }
+        match &origin.track {
+            Some(track) => repo.ref_store.set_head_to_track(track)?,
+            None => repo.ref_store.detach_head(&origin.head_hash)?,
+        }
+
+        repo.ref_store.clear_integration()?;
+        return Ok(());
+    }
+
+    // Restore HEAD state to wipe partial hydration and remove stale files.
+    if let Some(head_hash) = repo.ref_store.read_head()? {
+        let (_, content) = repo.object_store.read_object(&head_hash)?;
+        let snapshot = Snapshot::deserialize(&content)?;
+        let indexer = Indexer::new(&repo.object_store, &repo.root_path);
+        if let Some(current_tree_hash) = indexer.index_path(&repo.root_path)? {
+            hydrator.apply_diff(&current_tree_hash, &snapshot.directory_hash, &repo.root_path)?;
+        } else {
+            hydrator.hydrate_directory(&snapshot.directory_hash, &repo.root_path)?;
+        }
+    }
+
+    repo.ref_store.clear_integration()?;
+    Ok(())
+}
+
+/// Checks that every conflict marker of a paused operation was removed, records the
+/// resolutions of text conflicts and indexes the resolved workspace.
+pub(super) fn resolved_tree(repo: &JogenRepo, status: &IntegrationStatus) -> Result<String> {
+    // Check only the conflict markers recorded for this operation.
+    let mut conflicts_remain = false;
+    for conflict_path in &status.conflict_paths {
+        let absolute = repo.root_path.join(conflict_path);
+        if absolute.exists() {
+            println!("  - {}", conflict_path.red());
+            conflicts_remain = true;
+        }
+    }
+
+    if conflicts_remain {
+        return Err(anyhow::anyhow!("Cannot continue. Unresolved '.incoming' files still exist."));
+    }
+
+    // Remember how each text conflict was resolved so later merges can reuse it.
+    let cache = ResolutionCache::new(&repo.object_store, &repo.root_path);
+    for (key, path) in &status.recorded_conflicts {
+        if let Ok(resolved) = std::fs::read_to_string(repo.root_path.join(path)) {
+            cache.record(key, &resolved)?;
+        }
+    }
+
+    let indexer = Indexer::new(&repo.object_store, &repo.root_path);
+    indexer
+        .index_path(&repo.root_path)?
+        .ok_or_else(|| anyhow::anyhow!("Workspace is empty"))
+}
+
 pub fn create_track(name: String, switch: bool) -> Result<()> {
     let repo = JogenRepo::from_cwd()?;
 
@@ -417,6 +1147,50 @@ pub fn create_track(name: String, switch: bool) -> Result<()> {
     Ok(())
 }
 
+pub fn create_label(name: String, revision: Option<String>) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+
+    let hash = repo.resolve_revision(revision.as_deref().unwrap_or("HEAD"))?;
+    repo.ref_store.create_label(&name, &hash)?;
+
+    println!("{} Created label {} at {}", "✔".green(), name.yellow(), hash[..7].cyan());
+
+    Ok(())
+}
+
+pub fn list_labels() -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+
+    let labels = repo.ref_store.list_labels()?;
+
+    if labels.is_empty() {
+        println!("{}", "No labels found.".dimmed());
+    }
+
+    for label in labels {
+        let hash = repo.ref_store.resolve_label(&label)?.unwrap_or_default();
+        println!("  {} {}", label, hash.get(..7).unwrap_or(&hash).dimmed());
+    }
+
+    Ok(())
+}
+
+pub fn list_contexts() -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+    let config = Config::load(&repo.root_path)?;
+
+    for info in config.contexts() {
+        let kind = if info.context.is_built_in() { "" } else { " (custom)" };
+        print!("  {:<10} {}{}", info.context.as_str().yellow(), info.description, kind.dimmed());
+        if !info.requires.is_empty() {
+            print!(" {}", format!("[requires {}]", info.requires.join(", ")).dimmed());
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
 pub fn list_tracks() -> Result<()> {
     let repo = JogenRepo::from_cwd()?;
 
@@ -438,9 +1212,13 @@ pub fn list_tracks() -> Result<()> {
     Ok(())
 }
 
-pub fn diff(file: std::path::PathBuf) -> Result<()> {
+pub fn diff(paths: Vec<std::path::PathBuf>) -> Result<()> {
     let repo = JogenRepo::from_cwd()?;
 
+    let [file] = <[std::path::PathBuf; 1]>::try_from(paths).map_err(|_| {
+        anyhow::anyhow!("Must provide exactly one conflicted file. Use --semantic to compare revisions.")
+    })?;
+
     let mut incoming_file = file.clone();
     if let Some(ext) = incoming_file.extension() {
         let new_ext = format!("incoming.{}", ext.to_string_lossy());
@@ -458,123 +1236,103 @@ pub fn diff(file: std::path::PathBuf) -> Result<()> {
 
     let mut base_content = Vec::new();
     if let Ok(Some(status)) = repo.ref_store.get_integration_status() {
-        if !status.base_hash.is_empty() {
-             // Find base tree
-             if let Ok((kind, content)) = repo.object_store.read_object(&status.base_hash) {
-                 if kind == ObjectType::Snapshot {
-                     if let Ok(snapshot) = Snapshot::deserialize(&content) {
-                          // Note: extracting the exact file from a tree requires a full tree walk.
-                          // For simplicity in this demo block-level merge, if we can't easily fetch it,
-                          // we might skip. But let's assume we can fetch it if we had a full tree walker.
-                          // As a shortcut, we might just try to find the blob hash via hydrator, or
-                          // keep it empty for now if it's too complex.
-                          // Since finding the exact blob hash for a file requires traversing the Directory objects,
-                          // let's do a quick traversal.
-                          if let Ok(blob_hash) = find_blob_in_tree(&repo.object_store, &snapshot.directory_hash, &file, &repo.root_path) {
-                              if let Ok((_, blob_data)) = repo.object_store.read_object(&blob_hash) {
-                                  base_content = blob_data;
-                              }
-                          }
-                     }
-                 }
-             }
-        }
-    }
-
-    let semantic_engine = jogen_core::semantic::SemanticEngine::new();
+        // The recorded base is a snapshot, or the tree an integration merged against.
+        let base_tree = match repo.object_store.read_object(&status.base_hash) {
+            Ok((ObjectType::Snapshot, content)) => Snapshot::deserialize(&content).ok().map(|snapshot| snapshot.directory_hash),
+            Ok((ObjectType::Directory, _)) => Some(status.base_hash.clone()),
+            _ => None,
+        };
+        if let Some(base_tree) = base_tree {
+            if let Ok(blob_hash) = find_blob_in_tree(&repo.object_store, &base_tree, &file, &repo.root_path) {
+                if let Ok((_, blob_data)) = repo.object_store.read_object(&blob_hash) {
+                    base_content = blob_data;
+                }
+            }
+        }
+    }
+
+    let semantic_engine = jogen_core::semantic::SemanticEngine::new()?;
 
     let head_parsed = semantic_engine.parse_file(&file, &head_content);
     let target_parsed = semantic_engine.parse_file(&incoming_file, &target_content);
     let base_parsed = if !base_content.is_empty() { semantic_engine.parse_file(&file, &base_content) } else { None };
 
-    if let (Some((_, head_tree)), Some((_, target_tree))) = (head_parsed, target_parsed) {
+    if let (Some((language, head_tree)), Some((_, target_tree))) = (head_parsed, target_parsed) {
         println!("{} Semantic Diff: {}", "★".purple(), file.display().to_string().bold());
         println!("{}", "=".repeat(40).dimmed());
 
-        let head_blocks = semantic_engine.extract_blocks(&head_tree, &head_content);
-        let target_blocks = semantic_engine.extract_blocks(&target_tree, &target_content);
+        let head_blocks = semantic_engine.extract_blocks(language, &head_tree, &head_content);
+        let target_blocks = semantic_engine.extract_blocks(language, &target_tree, &target_content);
         
         let base_blocks = if let Some((_, base_tree)) = base_parsed {
-            semantic_engine.extract_blocks(&base_tree, &base_content)
+            semantic_engine.extract_blocks(language, &base_tree, &base_content)
         } else {
             Vec::new()
         };
 
-        let mut head_map = std::collections::HashMap::new();
-        for block in head_blocks {
-            head_map.insert(format!("{}:{}", block.kind, block.name), block);
-        }
-
-        let mut target_map = std::collections::HashMap::new();
-        for block in target_blocks {
-            target_map.insert(format!("{}:{}", block.kind, block.name), block);
-        }
-        
-        let mut base_map = std::collections::HashMap::new();
-        for block in base_blocks {
-            base_map.insert(format!("{}:{}", block.kind, block.name), block);
-        }
-
-        let all_keys: std::collections::HashSet<_> = head_map.keys().chain(target_map.keys()).cloned().collect();
-        let mut sorted_keys: Vec<_> = all_keys.into_iter().collect();
-        sorted_keys.sort();
-
-        let mut found_changes = false;
-
-        for key in sorted_keys {
-            let head_block = head_map.get(&key);
-            let target_block = target_map.get(&key);
-            let base_block = base_map.get(&key);
-
-            let print_header = |prefix: colored::ColoredString, block: &jogen_core::semantic::SemanticBlock| {
-                let crumbs = if block.breadcrumbs.len() > 1 {
-                    format!(" [{}] ", block.breadcrumbs[..block.breadcrumbs.len()-1].join(" > ").dimmed())
-                } else {
-                    " ".to_string()
-                };
-                println!("{} {}{}'{}' (Lines {}-{})", prefix, block.kind, crumbs, block.name.bold(), block.start_line, block.end_line);
+        let print_header = |prefix: colored::ColoredString, block: &SemanticBlock| {
+            let crumbs = if block.breadcrumbs.is_empty() {
+                " ".to_string()
+            } else {
+                format!(" [{}] ", block.breadcrumbs.join(" > ").dimmed())
             };
+            println!("{} {}{}'{}' (Lines {}-{})", prefix, block.kind, crumbs, block.name.bold(), block.start_line, block.end_line);
+        };
 
-            match (head_block, target_block) {
-                (Some(h), Some(t)) => {
-                    if h.content != t.content {
-                        print_header("Mod".yellow().bold(), h);
-                        println!("    {} Your version differs from the incoming version.", "→".dimmed());
-                        
-                        if let Some(b) = base_block {
-                             println!("    {} Original Base version:", "→".dimmed());
-                             for line in b.content.lines() {
-                                 println!("      {}", line.dimmed());
-                             }
-                        }
+        let changes = diff_blocks(&head_blocks, &target_blocks);
+        let found_changes = !changes.is_empty();
+
+        for change in changes {
+            match (change.kind, &change.old, &change.new) {
+                (SymbolChangeKind::Modified, Some(h), Some(t)) => {
+                    print_header("Mod".yellow().bold(), h);
+                    println!("    {} Your version differs from the incoming version.", "→".dimmed());
+
+                    let base_block = base_blocks
+                        .iter()
+                        .find(|b| b.kind == h.kind && b.name == h.name && b.breadcrumbs == h.breadcrumbs);
+                    if let Some(b) = base_block {
+                         println!("    {} Original Base version:", "→".dimmed());
+                         for line in b.content.lines() {
+                             println!("      {}", line.dimmed());
+                         }
+                    }
 
-                        println!("    {} Changes (Yours vs Incoming):", "→".dimmed());
-                        let diff = similar::TextDiff::from_lines(&h.content, &t.content);
-                        for change in diff.iter_all_changes() {
-                            let sign = match change.tag() {
-                                similar::ChangeTag::Delete => "-".red(),
-                                similar::ChangeTag::Insert => "+".green(),
-                                similar::ChangeTag::Equal => " ".dimmed(),
-                            };
-                            let mut line_str = change.value().to_string();
-                            if line_str.ends_with('\n') {
-                                line_str.pop();
-                            }
-                            if line_str.ends_with('\r') {
-                                line_str.pop();
-                            }
-                            match change.tag() {
-                                similar::ChangeTag::Delete => println!("      {} {}", sign, line_str.red().dimmed()),
-                                similar::ChangeTag::Insert => println!("      {} {}", sign, line_str.green().dimmed()),
-                                similar::ChangeTag::Equal => println!("      {} {}", sign, line_str.dimmed()),
-                            }
+                    println!("    {} Changes (Yours vs Incoming):", "→".dimmed());
+                    let diff = similar::TextDiff::from_lines(&h.content, &t.content);
+                    for change in diff.iter_all_changes() {
+                        let sign = match change.tag() {
+                            similar::ChangeTag::Delete => "-".red(),
+                            similar::ChangeTag::Insert => "+".green(),
+                            similar::ChangeTag::Equal => " ".dimmed(),
+                        };
+                        let mut line_str = change.value().to_string();
+                        if line_str.ends_with('\n') {
+                            line_str.pop();
+                        }
+                        if line_str.ends_with('\r') {
+                            line_str.pop();
+                        }
+                        match change.tag() {
+                            similar::ChangeTag::Delete => println!("      {} {}", sign, line_str.red().dimmed()),
+                            similar::ChangeTag::Insert => println!("      {} {}", sign, line_str.green().dimmed()),
+                            similar::ChangeTag::Equal => println!("      {} {}", sign, line_str.dimmed()),
                         }
-                        
-                        println!();
-                        found_changes = true;
                     }
+
+                    println!();
+                }
+                (SymbolChangeKind::Moved, Some(h), Some(t)) => {
+                    print_header("Mov".cyan().bold(), t);
+                    println!(
+                        "    {} This block was moved in the incoming track (Lines {}-{} in your version).",
+                        "→".dimmed(),
+                        h.start_line,
+                        h.end_line
+                    );
+                    println!();
                 }
-                (Some(h), None) => {
+                (SymbolChangeKind::Removed, Some(h), _) => {
                     print_header("Del".red().bold(), h);
                     println!("    {} This block was removed in the incoming track.", "→".dimmed());
                     println!("    {} Removed code:", "→".dimmed());
@@ -582,9 +1340,8 @@ pub fn diff(file: std::path::PathBuf) -> Result<()> {
                         println!("      {}", line.red().dimmed());
                     }
                     println!();
-                    found_changes = true;
                 }
-                (None, Some(t)) => {
+                (SymbolChangeKind::Added, _, Some(t)) => {
                     print_header("Add".green().bold(), t);
                     println!("    {} This block was added in the incoming track.", "→".dimmed());
                     println!("    {} Added code:", "→".dimmed());
@@ -592,9 +1349,8 @@ pub fn diff(file: std::path::PathBuf) -> Result<()> {
                         println!("      {}", line.green().dimmed());
                     }
                     println!();
-                    found_changes = true;
                 }
-                (None, None) => {}
+                _ => {}
             }
         }
 
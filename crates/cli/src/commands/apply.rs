@@ -0,0 +1,260 @@
+//! Commands that apply the change introduced by an existing snapshot onto HEAD.
+
+use anyhow::Result;
+use chrono::Utc;
+use colored::*;
+
+use crate::{
+    args::{PortArgs, RevertArgs},
+    commands::{
+        actions::{abort_paused_operation, ensure_clean_workspace, pause_on_conflicts, resolved_tree},
+        JogenRepo,
+    },
+};
+
+use jogen_core::{
+    hydrator::Hydrator,
+    merge::MergeEngine,
+    objects::snapshot::{Snapshot, SnapshotContext},
+    ref_store::IntegrationOperation,
+};
+
+pub fn port(args: PortArgs) -> Result<()> {
+    apply(
+        IntegrationOperation::Port,
+        args.revision,
+        None,
+        args.r#continue,
+        args.abort,
+    )
+}
+
+pub fn revert(args: RevertArgs) -> Result<()> {
+    apply(
+        IntegrationOperation::Revert,
+        args.revision,
+        args.mainline,
+        args.r#continue,
+        args.abort,
+    )
+}
+
+/// Shared flow for `port` and `revert`: three-way merge a single snapshot's change
+/// (or its inverse) onto HEAD, pausing on conflicts like `integrate`.
+fn apply(
+    operation: IntegrationOperation,
+    revision: Option<String>,
+    mainline: Option<usize>,
+    resume: bool,
+    abort: bool,
+) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+    let name = operation.as_str();
+
+    // --- HANDLE ABORT ---
+    if abort {
+        let Some(status) = repo.ref_store.get_integration_status()? else {
+            println!("{} Nothing to abort: no {} in progress.", "ℹ".blue(), name);
+            return Ok(());
+        };
+
+        println!("{} Aborting {}...", "⚠".yellow(), status.operation.as_str());
+        abort_paused_operation(&repo, &status)?;
+
+        println!("{} Aborted {}. Workspace restored.", "✔".green(), status.operation.as_str());
+        return Ok(());
+    }
+
+    let head_hash = repo
+        .ref_store
+        .read_head()?
+        .ok_or_else(|| anyhow::anyhow!("Head is empty. Cannot {}.", name))?;
+
+    // --- HANDLE CONTINUE ---
+    if resume {
+        let status = repo
+            .ref_store
+            .get_integration_status()?
+            .ok_or_else(|| anyhow::anyhow!("No {} in progress.", name))?;
+
+        if status.operation != operation {
+            return Err(anyhow::anyhow!(
+                "A {} is in progress. Use 'jogen {} --continue' instead.",
+                status.operation.as_str(),
+                status.operation.as_str()
+            ));
+        }
+
+        let resolved_tree_hash = resolved_tree(&repo, &status)?;
+
+        println!("{} Finalizing {}...", "⚙".blue(), name);
+
+        let source = repo.load_snapshot(&status.target_hash)?;
+        let snapshot_hash = record(
+            &repo,
+            operation,
+            &status.target_hash,
+            &source,
+            resolved_tree_hash,
+            head_hash,
+        )?;
+        repo.ref_store.clear_integration()?;
+
+        println!("{} {} complete. Created snapshot {}", "✔".green(), capitalize(name), snapshot_hash[..7].yellow());
+        return Ok(());
+    }
+
+    // --- HANDLE NEW OPERATION ---
+    if let Some(status) = repo.ref_store.get_integration_status()? {
+        return Err(anyhow::anyhow!(
+            "A {} is already in progress. Use --continue or --abort.",
+            status.operation.as_str()
+        ));
+    }
+
+    let revision = revision.ok_or_else(|| anyhow::anyhow!("Must provide a snapshot to {}.", name))?;
+    let source_hash = repo.resolve_revision(&revision)?;
+    let source = repo.load_snapshot(&source_hash)?;
+
+    // The change is the source snapshot relative to one of its parents: the first one,
+    // unless a mainline parent is chosen for a merge snapshot.
+    let parent_index = match (mainline, source.parent_hashes.len()) {
+        (Some(_), 0 | 1) => {
+            return Err(anyhow::anyhow!(
+                "Snapshot {} is not a merge; --mainline only applies to merge snapshots.",
+                &source_hash[..7]
+            ))
+        }
+        (Some(n), count) if n == 0 || n > count => {
+            return Err(anyhow::anyhow!(
+                "Snapshot {} has {} parents; --mainline must be between 1 and {}.",
+                &source_hash[..7],
+                count,
+                count
+            ))
+        }
+        (Some(n), _) => n - 1,
+        (None, count) if count > 1 && operation == IntegrationOperation::Revert => {
+            return Err(anyhow::anyhow!(
+                "Snapshot {} is a merge. Use --mainline <n> to choose the parent whose side is kept.",
+                &source_hash[..7]
+            ))
+        }
+        (None, _) => 0,
+    };
+
+    let head_tree = repo.load_snapshot(&head_hash)?.directory_hash;
+    let action = match operation {
+        IntegrationOperation::Revert => "reverting",
+        _ => "porting",
+    };
+    ensure_clean_workspace(&repo, &head_tree, action)?;
+
+    println!("{} {} {} onto HEAD...", "⚙".blue(), capitalize(action), source_hash[..7].yellow());
+
+    let parent_hash = source.parent_hashes.get(parent_index).cloned();
+    let parent_tree = match &parent_hash {
+        Some(hash) => Some(repo.load_snapshot(hash)?.directory_hash),
+        None => None,
+    };
+
+    // Porting replays parent -> source; reverting replays source -> parent.
+    let (base_hash, base_tree, target_tree) = match operation {
+        IntegrationOperation::Revert => (
+            Some(source_hash.clone()),
+            Some(source.directory_hash.clone()),
+            parent_tree,
+        ),
+        _ => (parent_hash, parent_tree, Some(source.directory_hash.clone())),
+    };
+
+    let merge_engine = MergeEngine::new(&repo.object_store);
+    let merge_result = merge_engine.merge_trees(
+        base_tree.as_deref(),
+        Some(&head_tree),
+        target_tree.as_deref(),
+        "",
+    )?;
+    let merged_tree_hash = merge_result
+        .tree_hash
+        .ok_or_else(|| anyhow::anyhow!("Merge failed to produce a tree hash."))?;
+
+    if merge_result.conflicts.is_empty() && merged_tree_hash == head_tree {
+        println!("{} Nothing to {}: HEAD already has the resulting changes.", "✔".green(), name);
+        return Ok(());
+    }
+
+    let hydrator = Hydrator::new(&repo.object_store);
+    hydrator.apply_diff(&head_tree, &merged_tree_hash, &repo.root_path)?;
+
+    if !merge_result.conflicts.is_empty() {
+        println!("{} Conflicts found! Pausing {}.", "⚠".yellow().bold(), name);
+
+        pause_on_conflicts(
+            &repo,
+            &hydrator,
+            merge_result.conflicts,
+            base_hash.as_deref().unwrap_or(""),
+            &source_hash,
+            &revision,
+            operation,
+        )?;
+
+        return Err(anyhow::anyhow!("{} paused due to conflicts.", capitalize(name)));
+    }
+
+    let snapshot_hash = record(&repo, operation, &source_hash, &source, merged_tree_hash, head_hash)?;
+
+    println!("{} {} complete. Created snapshot {}", "✔".green(), capitalize(name), snapshot_hash[..7].yellow());
+    Ok(())
+}
+
+/// Records the applied change as a new snapshot on HEAD.
+///
+/// A port keeps the source's author, context and message and references it with `ported-from`.
+/// A revert gets the `revert` context and references the original with `reverts`.
+fn record(
+    repo: &JogenRepo,
+    operation: IntegrationOperation,
+    source_hash: &str,
+    source: &Snapshot,
+    tree_hash: String,
+    head_hash: String,
+) -> Result<String> {
+    let snapshot = match operation {
+        IntegrationOperation::Revert => {
+            let summary = source.message.lines().next().unwrap_or_default();
+            Snapshot::new(
+                tree_hash,
+                vec![head_hash],
+                "Jogen User <user@jogen.com>".to_string(),
+                Utc::now().timestamp(),
+                SnapshotContext::Revert,
+                format!("Revert \"{}\"\n\nThis reverts snapshot {}.", summary, source_hash),
+            )
+            .with_header("reverts", source_hash)
+        }
+        _ => Snapshot::new(
+            tree_hash,
+            vec![head_hash],
+            source.author.clone(),
+            Utc::now().timestamp(),
+            source.context.clone(),
+            source.message.clone(),
+        )
+        .with_header("ported-from", source_hash),
+    };
+
+    let snapshot_hash = repo.write_snapshot(&snapshot)?;
+    repo.ref_store.update_head(&snapshot_hash)?;
+
+    Ok(snapshot_hash)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
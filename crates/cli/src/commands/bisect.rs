@@ -0,0 +1,263 @@
+//! Commands that binary-search history for the snapshot that introduced a bug.
+
+use anyhow::Result;
+use colored::*;
+
+use crate::commands::{actions::ensure_clean_workspace, JogenRepo};
+
+use jogen_core::{
+    bisect::{BisectStep, Bisector},
+    hydrator::Hydrator,
+    objects::snapshot::Snapshot,
+    ref_store::{BisectState, BisectVerdict},
+};
+
+/// Exit status a `bisect run` command uses to skip a snapshot that cannot be tested.
+const SKIP_EXIT_CODE: i32 = 125;
+
+pub fn start(bad: String, good: Vec<String>) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+
+    if repo.ref_store.get_bisect_state()?.is_some() {
+        return Err(anyhow::anyhow!(
+            "A bisect is already in progress. Use 'jogen bisect reset' to stop it."
+        ));
+    }
+    if let Some(status) = repo.ref_store.get_integration_status()? {
+        return Err(anyhow::anyhow!(
+            "A {} is in progress. Finish or abort it before bisecting.",
+            status.operation.as_str()
+        ));
+    }
+
+    let head_hash = repo
+        .ref_store
+        .read_head()?
+        .ok_or_else(|| anyhow::anyhow!("Head is empty. Nothing to bisect."))?;
+    ensure_clean_workspace(&repo, &repo.load_snapshot(&head_hash)?.directory_hash, "bisecting")?;
+
+    let mut verdicts = vec![(BisectVerdict::Bad, repo.resolve_revision(&bad)?)];
+    for revision in &good {
+        verdicts.push((BisectVerdict::Good, repo.resolve_revision(revision)?));
+    }
+
+    let state = BisectState {
+        origin_track: repo.ref_store.current_track()?,
+        origin_hash: head_hash,
+        verdicts,
+    };
+
+    // Validate the range before saving anything.
+    let graph = repo.graph()?;
+    Bisector::new(&graph).next_step(
+        state.bad().unwrap_or_default(),
+        &state.marked(BisectVerdict::Good),
+        &[],
+    )?;
+
+    repo.ref_store.write_bisect_state(&state)?;
+    println!("{} Bisecting between {} good and 1 bad snapshot.", "⚙".blue(), good.len());
+
+    advance(&repo, &state)?;
+    Ok(())
+}
+
+pub fn mark(verdict: BisectVerdict, revision: Option<String>) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+    let mut state = bisect_state(&repo)?;
+
+    let hash = match revision {
+        Some(revision) => repo.resolve_revision(&revision)?,
+        None => repo
+            .ref_store
+            .read_head()?
+            .ok_or_else(|| anyhow::anyhow!("Head is empty."))?,
+    };
+
+    state.verdicts.push((verdict, hash));
+    repo.ref_store.write_bisect_state(&state)?;
+
+    advance(&repo, &state)?;
+    Ok(())
+}
+
+/// Tests each snapshot chosen by the search with `command`: exit status 0 marks it good,
+/// 125 skips it, any other status up to 127 marks it bad. Anything else stops the run.
+pub fn run(command: Vec<String>) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+    let mut state = bisect_state(&repo)?;
+
+    let (program, arguments) = command
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("Must provide a command to run."))?;
+
+    // `start` and `mark` already checked out the snapshot to test, so test HEAD first.
+    let head_hash = repo
+        .ref_store
+        .read_head()?
+        .ok_or_else(|| anyhow::anyhow!("Head is empty."))?;
+    let mut hash = match next_step(&repo, &state)? {
+        BisectStep::Test { hash, .. } if hash == head_hash => hash,
+        _ => match advance(&repo, &state)? {
+            Some(hash) => hash,
+            None => return Ok(()),
+        },
+    };
+
+    loop {
+        println!("{} Running {}", "⚙".blue(), command.join(" ").dimmed());
+        let status = std::process::Command::new(program)
+            .args(arguments)
+            .status()
+            .map_err(|e| anyhow::anyhow!("Could not run '{}': {}", program, e))?;
+
+        let verdict = match status.code() {
+            Some(0) => BisectVerdict::Good,
+            Some(SKIP_EXIT_CODE) => BisectVerdict::Skip,
+            Some(code) if (1..128).contains(&code) => BisectVerdict::Bad,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Bisect run stopped: '{}' exited with {} at {}. The bisect is still in progress.",
+                    command.join(" "),
+                    status,
+                    &hash[..7]
+                ))
+            }
+        };
+
+        println!("{} {} is {}", "→".dimmed(), hash[..7].yellow(), verdict.as_str().bold());
+        state.verdicts.push((verdict, hash));
+        repo.ref_store.write_bisect_state(&state)?;
+
+        hash = match advance(&repo, &state)? {
+            Some(hash) => hash,
+            None => return Ok(()),
+        };
+    }
+}
+
+pub fn reset() -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+    let Some(state) = repo.ref_store.get_bisect_state()? else {
+        println!("{} Nothing to reset: no bisect in progress.", "ℹ".blue());
+        return Ok(());
+    };
+
+    move_head(&repo, &state.origin_hash)?;
+    match &state.origin_track {
+        Some(track) => repo.ref_store.set_head_to_track(track)?,
+        None => repo.ref_store.detach_head(&state.origin_hash)?,
+    }
+    repo.ref_store.clear_bisect()?;
+
+    let origin = state.origin_track.unwrap_or_else(|| state.origin_hash[..7].to_string());
+    println!("{} Bisect reset. Back on {}.", "✔".green(), origin.yellow());
+    Ok(())
+}
+
+fn bisect_state(repo: &JogenRepo) -> Result<BisectState> {
+    repo.ref_store
+        .get_bisect_state()?
+        .ok_or_else(|| anyhow::anyhow!("No bisect in progress. Start one with 'jogen bisect start <bad> <good>'."))
+}
+
+/// Computes the next step from the verdicts recorded so far, without touching the workspace.
+fn next_step(repo: &JogenRepo, state: &BisectState) -> Result<BisectStep> {
+    let bad = state
+        .bad()
+        .ok_or_else(|| anyhow::anyhow!("Bisect state has no bad snapshot."))?;
+
+    let graph = repo.graph()?;
+    Ok(Bisector::new(&graph).next_step(
+        bad,
+        &state.marked(BisectVerdict::Good),
+        &state.marked(BisectVerdict::Skip),
+    )?)
+}
+
+/// Computes the next step: checks out the next snapshot to test and returns it, or prints the
+/// final report and returns None.
+fn advance(repo: &JogenRepo, state: &BisectState) -> Result<Option<String>> {
+    match next_step(repo, state)? {
+        BisectStep::Test {
+            hash,
+            candidates,
+            steps,
+        } => {
+            move_head(repo, &hash)?;
+            repo.ref_store.detach_head(&hash)?;
+
+            let snapshot = repo.load_snapshot(&hash)?;
+            println!(
+                "{} {} candidates left (about {} more {}). Testing:",
+                "↻".blue(),
+                candidates,
+                steps,
+                if steps == 1 { "step" } else { "steps" }
+            );
+            println!("  {}", describe(&hash, &snapshot));
+            Ok(Some(hash))
+        }
+        BisectStep::Found(hash) => {
+            let snapshot = repo.load_snapshot(&hash)?;
+            println!("{} {} is the first bad snapshot", "✔".green(), hash.yellow().bold());
+            println!("Author:    {}", snapshot.author);
+            println!("Context:   {}", snapshot.context.as_str().yellow());
+            println!("Message:   {}", snapshot.message);
+            print_verdicts(repo, state)?;
+            Ok(None)
+        }
+        BisectStep::Ambiguous(candidates) => {
+            println!(
+                "{} Only skipped snapshots are left. The first bad snapshot is one of:",
+                "⚠".yellow()
+            );
+            for hash in &candidates {
+                println!("  {}", describe(hash, &repo.load_snapshot(hash)?));
+            }
+            print_verdicts(repo, state)?;
+            Ok(None)
+        }
+    }
+}
+
+fn print_verdicts(repo: &JogenRepo, state: &BisectState) -> Result<()> {
+    println!("\nTested:");
+    for (verdict, hash) in &state.verdicts {
+        let label = match verdict {
+            BisectVerdict::Good => verdict.as_str().green(),
+            BisectVerdict::Bad => verdict.as_str().red(),
+            BisectVerdict::Skip => verdict.as_str().dimmed(),
+        };
+        println!("  {:<4} {}", label, describe(hash, &repo.load_snapshot(hash)?));
+    }
+    println!("\nRun 'jogen bisect reset' to return to where you started.");
+    Ok(())
+}
+
+fn describe(hash: &str, snapshot: &Snapshot) -> String {
+    format!(
+        "{} {} {}",
+        hash[..7].green(),
+        format!("[{}]", snapshot.context).yellow(),
+        snapshot.message.lines().next().unwrap_or_default()
+    )
+}
+
+/// Replaces the workspace contents with the given snapshot, refusing to discard changes.
+fn move_head(repo: &JogenRepo, hash: &str) -> Result<()> {
+    let head_hash = repo
+        .ref_store
+        .read_head()?
+        .ok_or_else(|| anyhow::anyhow!("Head is empty."))?;
+    if head_hash == hash {
+        return Ok(());
+    }
+
+    let head_tree = repo.load_snapshot(&head_hash)?.directory_hash;
+    ensure_clean_workspace(repo, &head_tree, "bisecting")?;
+
+    let target_tree = repo.load_snapshot(hash)?.directory_hash;
+    Hydrator::new(&repo.object_store).apply_diff(&head_tree, &target_tree, &repo.root_path)?;
+    Ok(())
+}
@@ -0,0 +1,912 @@
+//! Commands that read and present snapshot history.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use colored::*;
+use regex::Regex;
+use serde_json::json;
+
+use crate::{
+    args::{BlameArgs, ChangelogArgs, HistoryArgs, LogArgs},
+    commands::JogenRepo,
+};
+
+use jogen_core::{
+    blame::Blamer,
+    graph::GraphTraversal,
+    objects::snapshot::{Snapshot, SnapshotContext},
+    semantic::{SemanticBlock, SemanticEngine, SymbolPath},
+    semantic_diff::{SemanticDiff, SymbolChange, SymbolChangeKind},
+    tree::{ChangeKind, TreeReader},
+};
+
+pub fn log(args: LogArgs) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+
+    let head_hash = repo
+        .ref_store
+        .read_head()?
+        .ok_or_else(|| anyhow::anyhow!("No snapshots found (head is empty)"))?;
+
+    let filter = LogFilter::from_args(&repo, &args)?;
+    let decorations = Decorations::load(&repo)?;
+
+    // Ancestry comes from the commit graph; full snapshots are only loaded for filtering and printing.
+    let graph = repo.graph()?;
+    let mut walk = if args.expand {
+        HistoryWalk::Topological(graph.topological_order(&head_hash)?.into())
+    } else {
+        HistoryWalk::FirstParent(Some(head_hash))
+    };
+
+    // Graph lines only make sense when every snapshot of the expanded history is drawn.
+    let mut renderer = (args.expand && !args.json && filter.is_empty()).then(GraphRenderer::default);
+
+    let mut shown = 0;
+    let mut json_entries = Vec::new();
+
+    while let Some(hash) = walk.next(&graph)? {
+        if args.limit.is_some_and(|limit| shown >= limit) {
+            break;
+        }
+
+        let snapshot = repo.load_snapshot(&hash)?;
+        if !filter.matches(&repo, &snapshot)? {
+            continue;
+        }
+        shown += 1;
+
+        if args.json {
+            json_entries.push(snapshot_json(&hash, &snapshot));
+            continue;
+        }
+
+        let decoration = decorations.describe(&hash);
+        let lines = if args.oneline {
+            let mut line = format!("{}{} {} {}", hash[..7].green(), decoration, format!("[{}]", snapshot.context).yellow(), summary(&snapshot.message));
+            if snapshot.parent_hashes.len() > 1 {
+                line.push_str(&format!(" {}", format!("(merge of {})", short_hashes(&snapshot.parent_hashes)).dimmed()));
+            }
+            vec![line]
+        } else if let Some(template) = &args.format {
+            render_template(template, &hash, &snapshot).lines().map(str::to_string).collect()
+        } else {
+            let mut lines = vec![
+                format!("{} {}{}", "Snapshot:".dimmed(), hash.green().bold(), decoration),
+                format!("Author:    {}", snapshot.author.yellow()),
+                format!("Timestamp: {}", snapshot.timestamp.to_string().yellow()),
+                format!("Context:   {}", snapshot.context.as_str().yellow()),
+            ];
+            if snapshot.parent_hashes.len() > 1 {
+                lines.push(format!("Merge:     {}", short_hashes(&snapshot.parent_hashes).dimmed()));
+            }
+            let mut message = snapshot.message.lines();
+            lines.push(format!("Message:   {}", message.next().unwrap_or_default()));
+            lines.extend(message.map(str::to_string));
+            lines.push(String::new());
+            lines
+        };
+
+        match renderer.as_mut() {
+            Some(renderer) => {
+                let rows = renderer.advance(&hash, &snapshot.parent_hashes);
+                for (index, line) in lines.iter().enumerate() {
+                    let prefix = if index == 0 { &rows.node } else { &rows.continuation };
+                    println!("{}", format!("{} {}", prefix, line).trim_end());
+                }
+                for transition in rows.transitions {
+                    println!("{}", transition);
+                }
+            }
+            None => {
+                for line in lines {
+                    println!("{}", line);
+                }
+            }
+        }
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&json_entries)?);
+    }
+
+    Ok(())
+}
+
+pub fn changelog(args: ChangelogArgs) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+
+    if !args.range.contains("..") {
+        return Err(anyhow::anyhow!("Expected a range like <from>..<to>, got '{}'", args.range));
+    }
+    let (from_hash, to_hash) = repo.resolve_range(&args.range)?;
+
+    let graph = repo.graph()?;
+
+    // Entries are listed oldest first. Integration snapshots are collapsed: the snapshots they brought in are part of the range
+    // themselves, so only their task links are kept.
+    let mut sections: Vec<(SnapshotContext, Vec<(String, Snapshot)>)> = Vec::new();
+    let mut tasks: Vec<String> = Vec::new();
+
+    for hash in graph.range(&from_hash, &to_hash)?.into_iter().rev() {
+        let snapshot = repo.load_snapshot(&hash)?;
+
+        for task in snapshot.header_values("task") {
+            if !tasks.iter().any(|known| known == task) {
+                tasks.push(task.to_string());
+            }
+        }
+
+        if snapshot.context == SnapshotContext::Merge || snapshot.parent_hashes.len() > 1 {
+            continue;
+        }
+
+        match sections.iter_mut().find(|(context, _)| *context == snapshot.context) {
+            Some((_, entries)) => entries.push((hash, snapshot)),
+            None => sections.push((snapshot.context.clone(), vec![(hash, snapshot)])),
+        }
+    }
+
+    sections.sort_by(|(a, _), (b, _)| section_rank(a).cmp(&section_rank(b)));
+
+    if args.json {
+        let sections: Vec<serde_json::Value> = sections
+            .iter()
+            .map(|(context, entries)| {
+                json!({
+                    "context": context.as_str(),
+                    "title": section_title(context),
+                    "snapshots": entries
+                        .iter()
+                        .map(|(hash, snapshot)| json!({
+                            "hash": hash,
+                            "author": snapshot.author,
+                            "timestamp": snapshot.timestamp,
+                            "summary": summary(&snapshot.message),
+                            "message": snapshot.message,
+                            "tasks": snapshot.header_values("task"),
+                        }))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        let changelog = json!({
+            "from": from_hash,
+            "to": to_hash,
+            "sections": sections,
+            "tasks": tasks,
+        });
+        println!("{}", serde_json::to_string_pretty(&changelog)?);
+        return Ok(());
+    }
+
+    println!("## Changes in {}", args.range);
+
+    if sections.is_empty() {
+        println!("\nNo changes.");
+    }
+
+    for (context, entries) in &sections {
+        println!("\n### {}\n", section_title(context));
+        for (hash, snapshot) in entries {
+            let linked = snapshot.header_values("task");
+            let linked = if linked.is_empty() { String::new() } else { format!(" [{}]", linked.join(", ")) };
+            println!("- {} ({}){}", summary(&snapshot.message), &hash[..7], linked);
+        }
+    }
+
+    if !tasks.is_empty() {
+        println!("\n### Tasks\n");
+        for task in &tasks {
+            println!("- {}", task);
+        }
+    }
+
+    Ok(())
+}
+
+/// Orders changelog sections: user-facing changes first, then custom contexts by name.
+fn section_rank(context: &SnapshotContext) -> (usize, &str) {
+    let rank = match context {
+        SnapshotContext::Feature => 0,
+        SnapshotContext::Fix => 1,
+        SnapshotContext::Refactor => 2,
+        SnapshotContext::Docs => 3,
+        SnapshotContext::Revert => 4,
+        SnapshotContext::Chore => 5,
+        SnapshotContext::Initial => 6,
+        SnapshotContext::Merge => 7,
+        SnapshotContext::Shelf | SnapshotContext::Custom(_) => 8,
+    };
+    (rank, context.as_str())
+}
+
+fn section_title(context: &SnapshotContext) -> String {
+    let title = match context {
+        SnapshotContext::Feature => "Features",
+        SnapshotContext::Fix => "Fixes",
+        SnapshotContext::Refactor => "Refactors",
+        SnapshotContext::Docs => "Documentation",
+        SnapshotContext::Revert => "Reverts",
+        SnapshotContext::Chore => "Chores",
+        SnapshotContext::Initial => "Initial",
+        SnapshotContext::Merge => "Merges",
+        SnapshotContext::Shelf => "Shelves",
+        SnapshotContext::Custom(name) => {
+            let mut chars = name.chars();
+            return match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            };
+        }
+    };
+    title.to_string()
+}
+
+pub fn blame(args: BlameArgs) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+
+    let revision = args.rev.as_deref().unwrap_or("HEAD");
+    let head_hash = repo.resolve_revision(revision)?;
+    let path = repo.relative_path(&args.path)?;
+
+    let graph = repo.graph()?;
+    let lines = Blamer::new(&repo.object_store, &graph).blame(&head_hash, &path, args.follow_merges)?;
+
+    let mut snapshots: HashMap<String, Snapshot> = HashMap::new();
+    for line in &lines {
+        if !snapshots.contains_key(&line.snapshot) {
+            snapshots.insert(line.snapshot.clone(), repo.load_snapshot(&line.snapshot)?);
+        }
+    }
+
+    let author_name = |snapshot: &Snapshot| -> String {
+        snapshot.author.split(" <").next().unwrap_or_default().to_string()
+    };
+    let context_width = snapshots.values().map(|s| s.context.as_str().len()).max().unwrap_or(0);
+    let author_width = snapshots.values().map(|s| author_name(s).chars().count()).max().unwrap_or(0);
+    let number_width = lines.len().to_string().len();
+
+    for (number, line) in lines.iter().enumerate() {
+        let snapshot = &snapshots[&line.snapshot];
+        let date = DateTime::from_timestamp(snapshot.timestamp, 0)
+            .map(|date| date.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| snapshot.timestamp.to_string());
+
+        let context = format!("{:<width$}", snapshot.context.as_str(), width = context_width);
+        let number = format!("{:>width$})", number + 1, width = number_width);
+
+        println!(
+            "{} {} {:<author_width$} {} {} {}",
+            line.snapshot[..7].yellow(),
+            context.cyan(),
+            author_name(snapshot),
+            date.dimmed(),
+            number.dimmed(),
+            line.content
+        );
+    }
+
+    Ok(())
+}
+
+pub fn symbol_history(args: HistoryArgs) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+
+    let symbol = SymbolPath::parse(&args.symbol)
+        .ok_or_else(|| anyhow::anyhow!("Invalid symbol path '{}'", args.symbol))?;
+    let revision = args.rev.as_deref().unwrap_or("HEAD");
+    let mut current_hash = repo.resolve_revision(revision)?;
+    let path = repo.relative_path(&args.path)?;
+
+    let mut tracker = SymbolTracker {
+        repo: &repo,
+        engine: SemanticEngine::new()?,
+        symbol,
+        path,
+        versions: HashMap::new(),
+    };
+
+    let mut current_snapshot = repo.load_snapshot(&current_hash)?;
+    let mut current_block = match tracker.matches_at(&current_snapshot)?.as_slice() {
+        [] => {
+            return Err(anyhow::anyhow!(
+                "Symbol '{}' not found in {} at {}",
+                args.symbol,
+                tracker.path,
+                revision
+            ))
+        }
+        [block] => block.clone(),
+        blocks => {
+            let locations: Vec<String> = blocks
+                .iter()
+                .map(|block| format!("{} at lines {}-{}", block.kind, block.start_line, block.end_line))
+                .collect();
+            return Err(anyhow::anyhow!(
+                "Symbol '{}' is ambiguous in {}: {}. Name an enclosing block, e.g. 'impl Type > name'.",
+                args.symbol,
+                tracker.path,
+                locations.join(", ")
+            ));
+        }
+    };
+
+    println!(
+        "{} History of '{}' in {}\n",
+        "★".purple(),
+        args.symbol.bold(),
+        tracker.path
+    );
+
+    // Follow first parents until the snapshot that introduced the block. Only snapshots that
+    // changed its content are shown; moving it within the file is not a change.
+    loop {
+        let parent = match current_snapshot.parent_hashes.first() {
+            Some(hash) => Some((hash.clone(), repo.load_snapshot(hash)?)),
+            None => None,
+        };
+        let parent_block = match &parent {
+            Some((_, snapshot)) => tracker.block_at(snapshot)?,
+            None => None,
+        };
+
+        if parent_block.as_ref().map(|block| &block.content) != Some(&current_block.content) {
+            print_symbol_change(&current_hash, &current_snapshot, parent_block.as_ref(), &current_block);
+        }
+
+        match (parent, parent_block) {
+            (Some((hash, snapshot)), Some(block)) => {
+                current_hash = hash;
+                current_snapshot = snapshot;
+                current_block = block;
+            }
+            _ => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds a symbol's block in successive versions of a file, parsing each version once.
+struct SymbolTracker<'a> {
+    repo: &'a JogenRepo,
+    engine: SemanticEngine,
+    symbol: SymbolPath,
+    path: String,
+    /// The blocks matching the symbol in each blob of the file, keyed by blob hash.
+    versions: HashMap<String, Vec<SemanticBlock>>,
+}
+
+impl SymbolTracker<'_> {
+    /// The symbol's block in a snapshot. If several blocks match, the first one is followed.
+    fn block_at(&mut self, snapshot: &Snapshot) -> Result<Option<SemanticBlock>> {
+        Ok(self.matches_at(snapshot)?.into_iter().next())
+    }
+
+    fn matches_at(&mut self, snapshot: &Snapshot) -> Result<Vec<SemanticBlock>> {
+        let Some(entry) = TreeReader::new(&self.repo.object_store).lookup(&snapshot.directory_hash, &self.path)? else {
+            return Ok(Vec::new());
+        };
+
+        if let Some(blocks) = self.versions.get(&entry.hash) {
+            return Ok(blocks.clone());
+        }
+
+        let (_, content) = self.repo.object_store.read_object(&entry.hash)?;
+        let Some((language, tree)) = self.engine.parse_file(std::path::Path::new(&self.path), &content) else {
+            return Err(anyhow::anyhow!("Unsupported language for symbol history: {}", self.path));
+        };
+        let blocks = self.engine.extract_blocks(language, &tree, &content);
+        let matches: Vec<SemanticBlock> = self.symbol.find(&blocks).into_iter().cloned().collect();
+
+        self.versions.insert(entry.hash, matches.clone());
+        Ok(matches)
+    }
+}
+
+fn print_symbol_change(hash: &str, snapshot: &Snapshot, old: Option<&SemanticBlock>, new: &SemanticBlock) {
+    println!(
+        "{} {} {}",
+        hash[..7].green(),
+        format!("[{}]", snapshot.context).yellow(),
+        summary(&snapshot.message)
+    );
+    println!("Author: {}", snapshot.author);
+    println!("Date:   {}", format_date(snapshot.timestamp));
+
+    let lines = format!("{}-{}", new.start_line, new.end_line);
+    match old {
+        Some(old) if old.start_line != new.start_line => {
+            println!("Lines:  {} (moved from {}-{})", lines, old.start_line, old.end_line)
+        }
+        Some(_) => println!("Lines:  {}", lines),
+        None => println!("Lines:  {} (added)", lines),
+    }
+    println!();
+
+    let old_content = old.map(|block| block.content.as_str()).unwrap_or_default();
+    let diff = similar::TextDiff::from_lines(old_content, new.content.as_str());
+    for change in diff.iter_all_changes() {
+        let line = change.value().trim_end_matches(['\n', '\r']);
+        match change.tag() {
+            similar::ChangeTag::Delete => println!("    {} {}", "-".red(), line.red()),
+            similar::ChangeTag::Insert => println!("    {} {}", "+".green(), line.green()),
+            similar::ChangeTag::Equal => println!("      {}", line.dimmed()),
+        }
+    }
+    println!();
+}
+
+/// Lists the symbols changed between two revisions, or by a single revision when only one is given.
+pub fn semantic_diff(revisions: Vec<String>, paths: Vec<PathBuf>) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+
+    let (old_hash, new_hash) = match revisions.as_slice() {
+        [old, new] => (Some(repo.resolve_revision(old)?), repo.resolve_revision(new)?),
+        [revision] => {
+            let hash = repo.resolve_revision(revision)?;
+            (repo.load_snapshot(&hash)?.parent_hashes.first().cloned(), hash)
+        }
+        _ => return Err(anyhow::anyhow!("Must provide one or two revisions to compare.")),
+    };
+
+    let old_tree = match &old_hash {
+        Some(hash) => Some(repo.load_snapshot(hash)?.directory_hash),
+        None => None,
+    };
+    let new_tree = repo.load_snapshot(&new_hash)?.directory_hash;
+
+    let mut filters = Vec::new();
+    for path in &paths {
+        let path = repo.relative_path(path)?;
+        // The project root selects everything.
+        if path.is_empty() {
+            filters.clear();
+            break;
+        }
+        filters.push(path);
+    }
+
+    let engine = SemanticEngine::new()?;
+    let files =
+        SemanticDiff::new(&repo.object_store, &engine).diff_trees(old_tree.as_deref(), Some(&new_tree), &filters)?;
+
+    let old_label = old_hash.as_deref().map(|hash| &hash[..7]).unwrap_or("(empty)");
+    println!(
+        "{} Symbol changes {}..{}\n",
+        "★".purple(),
+        old_label.yellow(),
+        new_hash[..7].yellow()
+    );
+
+    if files.is_empty() {
+        println!("No files changed.");
+        return Ok(());
+    }
+
+    let mut counts: HashMap<SymbolChangeKind, usize> = HashMap::new();
+    for file in &files {
+        let code = file.kind.as_char().to_string();
+        let code = match file.kind {
+            ChangeKind::Added => code.green(),
+            ChangeKind::Deleted => code.red(),
+            ChangeKind::Modified => code.yellow(),
+        };
+
+        let Some(symbols) = &file.symbols else {
+            println!("{} {} {}", code, file.path.bold(), "(not parsed)".dimmed());
+            continue;
+        };
+        println!("{} {}", code, file.path.bold());
+        if symbols.is_empty() {
+            println!("    {}", "no symbol changes".dimmed());
+        }
+
+        let kind_width = symbols.iter().map(|change| change.block().kind.len()).max().unwrap_or(0);
+        let name_width = symbols.iter().map(|change| symbol_name(change.block()).chars().count()).max().unwrap_or(0);
+        for change in symbols {
+            *counts.entry(change.kind).or_default() += 1;
+            print_symbol_diff_line(change, kind_width, name_width);
+        }
+    }
+
+    let count = |kind| counts.get(&kind).copied().unwrap_or(0);
+    println!(
+        "\n{} file(s) changed: {} added, {} removed, {} modified, {} moved",
+        files.len(),
+        count(SymbolChangeKind::Added),
+        count(SymbolChangeKind::Removed),
+        count(SymbolChangeKind::Modified),
+        count(SymbolChangeKind::Moved)
+    );
+
+    Ok(())
+}
+
+/// The block's name preceded by the names of its enclosing blocks, e.g. `Hydrator > new`.
+fn symbol_name(block: &SemanticBlock) -> String {
+    let mut names = block.breadcrumbs.clone();
+    names.push(block.name.clone());
+    names.join(" > ")
+}
+
+fn print_symbol_diff_line(change: &SymbolChange, kind_width: usize, name_width: usize) {
+    let block = change.block();
+    let code = change.kind.as_char().to_string();
+    let code = match change.kind {
+        SymbolChangeKind::Added => code.green(),
+        SymbolChangeKind::Removed => code.red(),
+        SymbolChangeKind::Modified => code.yellow(),
+        SymbolChangeKind::Moved => code.cyan(),
+    };
+
+    let mut location = format!("lines {}-{}", block.start_line, block.end_line);
+    if let (Some(old), Some(new)) = (&change.old, &change.new) {
+        if old.breadcrumbs != new.breadcrumbs {
+            let from = if old.breadcrumbs.is_empty() {
+                "top level".to_string()
+            } else {
+                old.breadcrumbs.join(" > ")
+            };
+            location.push_str(&format!(", moved from {}", from));
+        } else if change.kind == SymbolChangeKind::Moved {
+            location.push_str(&format!(", was lines {}-{}", old.start_line, old.end_line));
+        }
+    }
+
+    println!(
+        "  {} {:<kind_width$}  {:<name_width$}  {}",
+        code,
+        block.kind.dimmed(),
+        symbol_name(block).bold(),
+        location.dimmed()
+    );
+}
+
+/// Yields snapshot hashes starting at HEAD: first parents only, or every ancestor in topological order.
+enum HistoryWalk {
+    FirstParent(Option<String>),
+    Topological(VecDeque<String>),
+}
+
+impl HistoryWalk {
+    fn next(&mut self, graph: &GraphTraversal) -> Result<Option<String>> {
+        match self {
+            Self::FirstParent(next) => {
+                let Some(current) = next.take() else {
+                    return Ok(None);
+                };
+                *next = graph.node(&current)?.parents.into_iter().next();
+                Ok(Some(current))
+            }
+            Self::Topological(order) => Ok(order.pop_front()),
+        }
+    }
+}
+
+/// Track, label and HEAD names pointing at each snapshot.
+struct Decorations {
+    names: HashMap<String, Vec<String>>,
+}
+
+impl Decorations {
+    fn load(repo: &JogenRepo) -> Result<Self> {
+        let mut names: HashMap<String, Vec<String>> = HashMap::new();
+        let current_track = repo.ref_store.current_track()?;
+
+        if current_track.is_none() {
+            if let Some(head) = repo.ref_store.read_head()? {
+                names.entry(head).or_default().push("HEAD".cyan().bold().to_string());
+            }
+        }
+
+        for track in repo.ref_store.list_tracks()? {
+            if let Some(hash) = repo.ref_store.resolve_track(&track)? {
+                let name = if current_track.as_deref() == Some(track.as_str()) {
+                    format!("{} {}", "HEAD ->".cyan().bold(), track.green().bold())
+                } else {
+                    track.green().to_string()
+                };
+                names.entry(hash).or_default().push(name);
+            }
+        }
+
+        for label in repo.ref_store.list_labels()? {
+            if let Some(hash) = repo.ref_store.resolve_label(&label)? {
+                names.entry(hash).or_default().push(format!("label: {}", label).yellow().to_string());
+            }
+        }
+
+        Ok(Self { names })
+    }
+
+    /// Returns ` (HEAD -> main, feature, label: v1.0)` or an empty string.
+    fn describe(&self, hash: &str) -> String {
+        match self.names.get(hash) {
+            Some(names) => format!(" ({})", names.join(", ")),
+            None => String::new(),
+        }
+    }
+}
+
+/// Draws the history DAG as ASCII lanes, one column per line of descent.
+///
+/// Each lane holds the snapshot it is waiting for. A snapshot is drawn as `*` in its lane; its
+/// first parent continues in the same lane and further parents open lanes to its right. Lanes
+/// waiting for the same snapshot join with `/`, new lanes branch off with `\`.
+#[derive(Default)]
+struct GraphRenderer {
+    lanes: Vec<String>,
+}
+
+struct GraphRows {
+    /// Prefix for the snapshot's first output line.
+    node: String,
+    /// Prefix for its remaining output lines.
+    continuation: String,
+    /// Lines connecting this snapshot's lanes to the next one's.
+    transitions: Vec<String>,
+}
+
+impl GraphRenderer {
+    fn advance(&mut self, hash: &str, parents: &[String]) -> GraphRows {
+        let column = match self.lanes.iter().position(|lane| lane == hash) {
+            Some(column) => column,
+            None => {
+                self.lanes.push(hash.to_string());
+                self.lanes.len() - 1
+            }
+        };
+
+        let node = self.row(|index| if index == column { '*' } else { '|' }, self.lanes.len());
+
+        // Replace the snapshot's lane with its parents, dropping lanes that now wait for a
+        // snapshot already held by a lane further left.
+        let mut next_lanes: Vec<String> = Vec::new();
+        for (index, lane) in self.lanes.iter().enumerate() {
+            if index == column {
+                for parent in parents {
+                    if !next_lanes.contains(parent) {
+                        next_lanes.push(parent.clone());
+                    }
+                }
+            } else if !next_lanes.contains(lane) {
+                next_lanes.push(lane.clone());
+            }
+        }
+
+        let position = |target: &String| next_lanes.iter().position(|lane| lane == target).unwrap_or(0);
+        let mut edges: Vec<(usize, usize)> = Vec::new();
+        for (index, lane) in self.lanes.iter().enumerate() {
+            if index == column {
+                edges.extend(parents.iter().map(|parent| (column, position(parent))));
+            } else {
+                edges.push((index, position(lane)));
+            }
+        }
+
+        let width = self.lanes.len().max(next_lanes.len());
+        let continuation = self.row(
+            |index| if edges.iter().any(|(from, _)| *from == index) { '|' } else { ' ' },
+            self.lanes.len(),
+        );
+
+        let mut transitions = Vec::new();
+        while edges.iter().any(|(from, to)| from != to) {
+            let mut row = vec![' '; width * 2];
+            for (from, to) in edges.iter_mut() {
+                if *to > *from {
+                    row[*from * 2 + 1] = '\\';
+                    *from += 1;
+                } else if *to < *from {
+                    row[*from * 2 - 1] = '/';
+                    *from -= 1;
+                } else {
+                    row[*from * 2] = '|';
+                }
+            }
+            transitions.push(row.into_iter().collect::<String>().trim_end().to_string());
+        }
+
+        self.lanes = next_lanes;
+
+        GraphRows {
+            node,
+            continuation,
+            transitions,
+        }
+    }
+
+    /// Renders one row with a character per lane, padded so that text lines up after the graph.
+    fn row(&self, symbol: impl Fn(usize) -> char, lanes: usize) -> String {
+        let mut row: String = (0..lanes).flat_map(|index| [symbol(index), ' ']).collect();
+        row.pop();
+        row
+    }
+}
+
+/// Criteria a snapshot must meet to be shown by `log`. Every criterion that is set must match.
+struct LogFilter {
+    contexts: Vec<SnapshotContext>,
+    author: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+    message: Option<Regex>,
+    path: Option<String>,
+}
+
+impl LogFilter {
+    fn from_args(repo: &JogenRepo, args: &LogArgs) -> Result<Self> {
+        let message = match &args.grep {
+            Some(pattern) => Some(
+                Regex::new(pattern)
+                    .map_err(|e| anyhow::anyhow!("Invalid message pattern '{}': {}", pattern, e))?,
+            ),
+            None => None,
+        };
+
+        let path = match &args.path {
+            Some(path) => Some(repo.relative_path(path)?),
+            None => None,
+        };
+
+        Ok(Self {
+            contexts: args.contexts.clone(),
+            author: args.author.as_ref().map(|author| author.to_lowercase()),
+            since: args.since.as_deref().map(|date| parse_date(date, false)).transpose()?,
+            until: args.until.as_deref().map(|date| parse_date(date, true)).transpose()?,
+            message,
+            path,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.contexts.is_empty()
+            && self.author.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+            && self.message.is_none()
+            && self.path.is_none()
+    }
+
+    fn matches(&self, repo: &JogenRepo, snapshot: &Snapshot) -> Result<bool> {
+        if !self.contexts.is_empty() && !self.contexts.contains(&snapshot.context) {
+            return Ok(false);
+        }
+        if let Some(author) = &self.author {
+            if !snapshot.author.to_lowercase().contains(author) {
+                return Ok(false);
+            }
+        }
+        if self.since.is_some_and(|since| snapshot.timestamp < since)
+            || self.until.is_some_and(|until| snapshot.timestamp > until)
+        {
+            return Ok(false);
+        }
+        if let Some(message) = &self.message {
+            if !message.is_match(&snapshot.message) {
+                return Ok(false);
+            }
+        }
+        if let Some(path) = &self.path {
+            return touches_path(repo, snapshot, path);
+        }
+        Ok(true)
+    }
+}
+
+/// Returns true if the snapshot changed the file or directory at `path` compared to its first parent.
+fn touches_path(repo: &JogenRepo, snapshot: &Snapshot, path: &str) -> Result<bool> {
+    let tree_reader = TreeReader::new(&repo.object_store);
+    let current = tree_reader.lookup(&snapshot.directory_hash, path)?;
+
+    let previous = match snapshot.parent_hashes.first() {
+        Some(parent) => {
+            let parent_tree = repo.load_snapshot(parent)?.directory_hash;
+            tree_reader.lookup(&parent_tree, path)?
+        }
+        None => None,
+    };
+
+    Ok(current != previous)
+}
+
+/// Parses a date given as `YYYY-MM-DD` (local time), RFC 3339 or a Unix timestamp.
+/// A bare date means the start of that day, or its end when `end_of_day` is set.
+fn parse_date(value: &str, end_of_day: bool) -> Result<i64> {
+    if let Ok(timestamp) = value.parse::<i64>() {
+        return Ok(timestamp);
+    }
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date_time.timestamp());
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let time = if end_of_day {
+            date.and_hms_opt(23, 59, 59)
+        } else {
+            date.and_hms_opt(0, 0, 0)
+        };
+        if let Some(local) = time.and_then(|t| Local.from_local_datetime(&t).earliest()) {
+            return Ok(local.timestamp());
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "Invalid date '{}'. Use YYYY-MM-DD, RFC 3339 or a Unix timestamp.",
+        value
+    ))
+}
+
+fn short_hashes(hashes: &[String]) -> String {
+    hashes.iter().map(|hash| &hash[..hash.len().min(7)]).collect::<Vec<_>>().join(" ")
+}
+
+fn summary(message: &str) -> &str {
+    message.lines().next().unwrap_or_default()
+}
+
+fn format_date(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|date| date.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %z").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// Expands `{placeholder}`s in a `--format` template. Unknown placeholders are kept as written.
+fn render_template(template: &str, hash: &str, snapshot: &Snapshot) -> String {
+    let mut output = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let Some(length) = rest[start..].find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let placeholder = &rest[start + 1..start + length];
+        let value = match placeholder {
+            "hash" => hash.to_string(),
+            "short" => hash[..7].to_string(),
+            "author" => snapshot.author.clone(),
+            "timestamp" => snapshot.timestamp.to_string(),
+            "date" => format_date(snapshot.timestamp),
+            "context" => snapshot.context.to_string(),
+            "summary" => summary(&snapshot.message).to_string(),
+            "message" => snapshot.message.clone(),
+            "parents" => snapshot.parent_hashes.join(" "),
+            "tree" => snapshot.directory_hash.clone(),
+            _ => rest[start..=start + length].to_string(),
+        };
+        output.push_str(&value);
+        rest = &rest[start + length + 1..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+fn snapshot_json(hash: &str, snapshot: &Snapshot) -> serde_json::Value {
+    // Keys such as `task` can repeat, so each key maps to all of its values in order.
+    let mut headers = serde_json::Map::new();
+    for (key, value) in &snapshot.headers {
+        if let serde_json::Value::Array(values) = headers.entry(key.clone()).or_insert_with(|| json!([])) {
+            values.push(json!(value));
+        }
+    }
+
+    json!({
+        "hash": hash,
+        "tree": snapshot.directory_hash,
+        "parents": snapshot.parent_hashes,
+        "author": snapshot.author,
+        "timestamp": snapshot.timestamp,
+        "context": snapshot.context.as_str(),
+        "message": snapshot.message,
+        "headers": headers,
+    })
+}
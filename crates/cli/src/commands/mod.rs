@@ -1,9 +1,21 @@
 use anyhow::Result;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 
-use jogen_core::{object_store::ObjectStore, ref_store::RefStore};
+use jogen_core::{
+    commit_graph::CommitGraph,
+    graph::GraphTraversal,
+    object_store::{ObjectStore, ObjectType},
+    objects::{snapshot::Snapshot, JogenObject},
+    ref_store::RefStore,
+    revision::RevisionResolver,
+};
 
 pub mod actions;
+pub mod apply;
+pub mod bisect;
+pub mod history;
+pub mod search;
+pub mod shelve;
 pub mod tools;
 
 struct JogenRepo {
@@ -24,4 +36,74 @@ impl JogenRepo {
             ref_store,
         })
     }
+
+    /// Resolves a track, label, snapshot hash or prefix (with optional `~N`/`^N`) to a snapshot hash.
+    fn resolve_revision(&self, revision: &str) -> Result<String> {
+        let resolver = RevisionResolver::new(&self.object_store, &self.ref_store);
+        Ok(resolver.resolve(revision)?)
+    }
+
+    /// Resolves a `<from>..<to>` range to a pair of snapshot hashes. Either side defaults to HEAD.
+    fn resolve_range(&self, range: &str) -> Result<(String, String)> {
+        let resolver = RevisionResolver::new(&self.object_store, &self.ref_store);
+        Ok(resolver.resolve_range(range)?)
+    }
+
+    /// History traversal backed by the commit-graph cache.
+    fn graph(&self) -> Result<GraphTraversal<'_>> {
+        let commit_graph = CommitGraph::load(&self.root_path)?;
+        Ok(GraphTraversal::new(&self.object_store, commit_graph))
+    }
+
+    /// Reads and parses a snapshot object.
+    fn load_snapshot(&self, hash: &str) -> Result<Snapshot> {
+        let (kind, content) = self.object_store.read_object(hash)?;
+        if kind != ObjectType::Snapshot {
+            return Err(anyhow::anyhow!("Object {} is a {}, not a snapshot", hash, kind));
+        }
+        Ok(Snapshot::deserialize(&content)?)
+    }
+
+    /// Converts a path given on the command line (relative to the current directory) into a
+    /// `/`-separated path relative to the project root. The path does not need to exist.
+    fn relative_path(&self, path: &Path) -> Result<String> {
+        let absolute = std::env::current_dir()?.join(path);
+
+        let mut components: Vec<String> = Vec::new();
+        for component in absolute.components() {
+            match component {
+                Component::ParentDir => {
+                    components.pop();
+                }
+                Component::Normal(part) => components.push(part.to_string_lossy().to_string()),
+                _ => {}
+            }
+        }
+
+        let root: Vec<String> = self
+            .root_path
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+
+        if !components.starts_with(&root) {
+            return Err(anyhow::anyhow!("Path {} is outside the project", path.display()));
+        }
+        Ok(components[root.len()..].join("/"))
+    }
+
+    /// Writes a snapshot object and records it in the commit-graph cache.
+    fn write_snapshot(&self, snapshot: &Snapshot) -> Result<String> {
+        let hash = self
+            .object_store
+            .write_object(snapshot.serialize()?.as_ref(), ObjectType::Snapshot)?;
+
+        let mut commit_graph = CommitGraph::load(&self.root_path)?;
+        commit_graph.add_snapshot(&self.object_store, &hash, snapshot)?;
+
+        Ok(hash)
+    }
 }
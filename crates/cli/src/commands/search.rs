@@ -0,0 +1,210 @@
+//! Commands that search the code recorded in snapshots.
+
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::{Context, Result};
+use colored::*;
+use regex::RegexBuilder;
+
+use crate::{
+    args::{GrepArgs, SymbolsArgs},
+    commands::JogenRepo,
+};
+
+use jogen_core::{
+    grep::{GrepMatch, TreeGrep},
+    semantic::{SemanticBlock, SemanticEngine, SymbolPath},
+    symbol_index::{IndexedSymbol, SymbolIndex},
+};
+
+/// Lists the symbols of a snapshot matching a query. A query with a `>` or a keyword is a symbol
+/// path matched like `history --symbol`; otherwise it matches names containing it, ignoring case.
+pub fn symbols(args: SymbolsArgs) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+
+    let revision = args.rev.as_deref().unwrap_or("HEAD");
+    let hash = repo.resolve_revision(revision)?;
+    let tree_hash = repo.load_snapshot(&hash)?.directory_hash;
+
+    let engine = SemanticEngine::new()?;
+    let (symbols, stats) = SymbolIndex::new(&repo.object_store, &engine, &repo.root_path).symbols(&tree_hash)?;
+
+    let query = args.query.trim();
+    let matches: Vec<&IndexedSymbol> = if query.contains('>') || query.contains(char::is_whitespace) {
+        let path = SymbolPath::parse(query).ok_or_else(|| anyhow::anyhow!("Invalid symbol path '{}'", query))?;
+
+        // Symbol paths are matched file by file, against the blocks in file order.
+        let mut by_file: BTreeMap<&str, Vec<&IndexedSymbol>> = BTreeMap::new();
+        for symbol in &symbols {
+            by_file.entry(symbol.path.as_str()).or_default().push(symbol);
+        }
+
+        let mut matches = Vec::new();
+        for file_symbols in by_file.values() {
+            let blocks: Vec<SemanticBlock> = file_symbols.iter().map(|symbol| symbol.to_block()).collect();
+            for block in path.find(&blocks) {
+                let index = blocks
+                    .iter()
+                    .position(|candidate| std::ptr::eq(candidate, block))
+                    .unwrap_or_default();
+                matches.push(file_symbols[index]);
+            }
+        }
+        matches
+    } else {
+        let needle = query.to_lowercase();
+        symbols
+            .iter()
+            .filter(|symbol| symbol.name.to_lowercase().contains(&needle))
+            .collect()
+    };
+
+    let summary = format!("{} directories indexed, {} from cache", stats.built, stats.reused);
+
+    if matches.is_empty() {
+        println!("No symbols matching '{}' at {}.", query, &hash[..7]);
+        println!("{}", summary.dimmed());
+        return Ok(());
+    }
+
+    let locations: Vec<String> = matches
+        .iter()
+        .map(|symbol| format!("{}:{}-{}", symbol.path, symbol.start_line, symbol.end_line))
+        .collect();
+    let location_width = locations.iter().map(|location| location.chars().count()).max().unwrap_or(0);
+    let kind_width = matches.iter().map(|symbol| symbol.kind.len()).max().unwrap_or(0);
+
+    for (symbol, location) in matches.iter().zip(&locations) {
+        let mut names = symbol.breadcrumbs.clone();
+        names.push(symbol.name.clone());
+        let visibility = if symbol.public { " (public)" } else { "" };
+
+        let location = format!("{:<location_width$}", location);
+        let kind = format!("{:<kind_width$}", symbol.kind);
+        println!(
+            "{}  {}  {}{}",
+            location.cyan(),
+            kind.dimmed(),
+            names.join(" > ").bold(),
+            visibility.dimmed()
+        );
+    }
+
+    let files: HashSet<&str> = matches.iter().map(|symbol| symbol.path.as_str()).collect();
+    println!(
+        "\n{} symbol(s) in {} file(s) at {}. {}",
+        matches.len(),
+        files.len(),
+        &hash[..7],
+        summary.dimmed()
+    );
+
+    Ok(())
+}
+
+/// Searches the files of a snapshot for a pattern. With `--range`, lists instead the matching lines
+/// each snapshot of the range added, so the first snapshot listed is where a string appeared.
+pub fn grep(args: GrepArgs) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+
+    let pattern = if args.fixed_strings {
+        regex::escape(&args.pattern)
+    } else {
+        args.pattern.clone()
+    };
+    let pattern = RegexBuilder::new(&pattern)
+        .case_insensitive(args.ignore_case)
+        .build()
+        .with_context(|| format!("Invalid pattern '{}'", args.pattern))?;
+
+    let mut filters = Vec::new();
+    for path in &args.paths {
+        let path = repo.relative_path(path)?;
+        // The project root selects everything.
+        if path.is_empty() {
+            filters.clear();
+            break;
+        }
+        filters.push(path);
+    }
+
+    let grep = TreeGrep::new(&repo.object_store, &pattern);
+
+    let Some(range) = args.range else {
+        let revision = args.revision.as_deref().unwrap_or("HEAD");
+        let hash = repo.resolve_revision(revision)?;
+        let tree_hash = repo.load_snapshot(&hash)?.directory_hash;
+
+        let matches = grep.search(&tree_hash, &filters)?;
+        for found in &matches {
+            print_match(found);
+        }
+        println!(
+            "{}",
+            format!("{} match(es) in {} file(s) at {}", matches.len(), file_count(&matches), &hash[..7]).dimmed()
+        );
+        return Ok(());
+    };
+
+    // A single revision stands for its whole history.
+    let graph = repo.graph()?;
+    let hashes = if range.contains("..") {
+        let (from_hash, to_hash) = repo.resolve_range(&range)?;
+        graph.range(&from_hash, &to_hash)?
+    } else {
+        graph.topological_order(&repo.resolve_revision(&range)?)?
+    };
+
+    let mut total = 0;
+    let mut snapshots = 0;
+    for hash in hashes.into_iter().rev() {
+        let snapshot = repo.load_snapshot(&hash)?;
+        let parent_tree = match snapshot.parent_hashes.first() {
+            Some(parent) => Some(repo.load_snapshot(parent)?.directory_hash),
+            None => None,
+        };
+
+        let matches = grep.search_introduced(parent_tree.as_deref(), &snapshot.directory_hash, &filters)?;
+        if matches.is_empty() {
+            continue;
+        }
+
+        println!(
+            "{} {} {}",
+            hash[..7].green(),
+            format!("[{}]", snapshot.context).yellow(),
+            snapshot.message.lines().next().unwrap_or_default()
+        );
+        for found in &matches {
+            print!("  ");
+            print_match(found);
+        }
+        println!();
+
+        total += matches.len();
+        snapshots += 1;
+    }
+
+    println!(
+        "{}",
+        format!("{} added match(es) in {} snapshot(s) of {}", total, snapshots, range).dimmed()
+    );
+    Ok(())
+}
+
+fn print_match(found: &GrepMatch) {
+    let mut line = String::new();
+    let mut last = 0;
+    for range in &found.ranges {
+        line.push_str(&found.line[last..range.start]);
+        line.push_str(&found.line[range.clone()].red().bold().to_string());
+        last = range.end;
+    }
+    line.push_str(&found.line[last..]);
+
+    println!("{}:{}:{}", found.path.magenta(), found.line_number.to_string().green(), line);
+}
+
+fn file_count(matches: &[GrepMatch]) -> usize {
+    matches.iter().map(|found| found.path.as_str()).collect::<HashSet<_>>().len()
+}
@@ -0,0 +1,214 @@
+//! Commands that set uncommitted work aside without recording it in history.
+
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use colored::*;
+
+use crate::commands::{
+    actions::{ensure_clean_workspace, print_preview_changes},
+    JogenRepo,
+};
+
+use jogen_core::{
+    hydrator::Hydrator,
+    indexer::Indexer,
+    merge::MergeEngine,
+    object_store::ObjectType,
+    objects::{
+        snapshot::{Snapshot, SnapshotContext},
+        JogenObject,
+    },
+    tree::TreeReader,
+};
+
+/// Records the workspace as a shelf and restores the workspace to HEAD.
+///
+/// A shelf is a snapshot object with the `shelf` context whose parent is HEAD at the time. It is
+/// written to the object store but not to the commit graph, only `refs/shelves` points at it, and
+/// revisions never resolve to it.
+pub fn push(message: Option<String>) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+
+    if let Some(status) = repo.ref_store.get_integration_status()? {
+        return Err(anyhow::anyhow!(
+            "A {} is in progress. Finish or abort it before shelving.",
+            status.operation.as_str()
+        ));
+    }
+
+    let head_hash = repo
+        .ref_store
+        .read_head()?
+        .ok_or_else(|| anyhow::anyhow!("Head is empty. Nothing to shelve."))?;
+    let head = repo.load_snapshot(&head_hash)?;
+
+    let indexer = Indexer::new(&repo.object_store, &repo.root_path);
+    let workspace_tree = indexer
+        .index_path(&repo.root_path)?
+        .ok_or_else(|| anyhow::anyhow!("Workspace is empty. Nothing to shelve."))?;
+    if workspace_tree == head.directory_hash {
+        println!("{} No changes to shelve.", "ℹ".blue());
+        return Ok(());
+    }
+
+    let track = repo.ref_store.current_track()?;
+    let message = message.unwrap_or_else(|| {
+        format!(
+            "WIP on {}: {}",
+            track.as_deref().unwrap_or(&head_hash[..7]),
+            head.message.lines().next().unwrap_or_default()
+        )
+    });
+
+    let mut shelf = Snapshot::new(
+        workspace_tree.clone(),
+        vec![head_hash],
+        "Jogen User <user@jogen.com>".to_string(),
+        Utc::now().timestamp(),
+        SnapshotContext::Shelf,
+        message,
+    );
+    if let Some(track) = &track {
+        shelf = shelf.with_header("track", track);
+    }
+    let shelf_hash = repo
+        .object_store
+        .write_object(shelf.serialize()?.as_ref(), ObjectType::Snapshot)?;
+    let id = repo.ref_store.push_shelf(&shelf_hash)?;
+
+    let changes = TreeReader::new(&repo.object_store).diff(Some(&head.directory_hash), Some(&workspace_tree))?;
+    Hydrator::new(&repo.object_store).apply_diff(&workspace_tree, &head.directory_hash, &repo.root_path)?;
+
+    print_preview_changes(&format!("Shelved as {}:", id), &changes);
+    println!("\n{} Workspace restored to HEAD. Run 'jogen shelve apply {}' to bring the changes back.", "✔".green(), id);
+    Ok(())
+}
+
+pub fn list() -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+
+    let shelves = repo.ref_store.list_shelves()?;
+    if shelves.is_empty() {
+        println!("{} No shelves.", "ℹ".blue());
+        return Ok(());
+    }
+
+    for (id, hash) in shelves {
+        let shelf = repo.load_snapshot(&hash)?;
+        let base = shelf.parent_hashes.first().map(|hash| &hash[..7]).unwrap_or("(empty)");
+        let origin = match shelf.header("track") {
+            Some(track) => format!("on {} at {}", track, base),
+            None => format!("at {}", base),
+        };
+        let date = DateTime::from_timestamp(shelf.timestamp, 0)
+            .map(|date| date.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+
+        println!(
+            "{}  {}  {}",
+            format!("{:>3}", id).yellow(),
+            shelf.message.lines().next().unwrap_or_default(),
+            format!("({}, {})", origin, date).dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// Three-way merges a shelf onto HEAD: the base is the snapshot the shelf was pushed from.
+/// Nothing is written if the merge conflicts. The shelf is kept, so it can be applied again or
+/// dropped afterwards.
+pub fn apply(id: Option<usize>) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+    let (id, shelf_hash) = find_shelf(&repo, id)?;
+
+    if let Some(status) = repo.ref_store.get_integration_status()? {
+        return Err(anyhow::anyhow!(
+            "A {} is in progress. Finish or abort it before applying a shelf.",
+            status.operation.as_str()
+        ));
+    }
+
+    let head_hash = repo
+        .ref_store
+        .read_head()?
+        .ok_or_else(|| anyhow::anyhow!("Head is empty. Cannot apply a shelf."))?;
+    let head_tree = repo.load_snapshot(&head_hash)?.directory_hash;
+    ensure_clean_workspace(&repo, &head_tree, "applying a shelf")?;
+
+    let shelf = repo.load_snapshot(&shelf_hash)?;
+    let base_tree = match shelf.parent_hashes.first() {
+        Some(parent) => Some(repo.load_snapshot(parent)?.directory_hash),
+        None => None,
+    };
+
+    println!("{} Applying shelf {} onto HEAD...", "⚙".blue(), id.to_string().yellow());
+
+    let merge_result = MergeEngine::new(&repo.object_store).merge_trees(
+        base_tree.as_deref(),
+        Some(&head_tree),
+        Some(&shelf.directory_hash),
+        "",
+    )?;
+    if !merge_result.conflicts.is_empty() {
+        println!("{} The shelf conflicts with HEAD:", "⚠".yellow().bold());
+        for conflict in &merge_result.conflicts {
+            println!("  - {} {}", conflict.path.red(), format!("({})", conflict.kind).dimmed());
+        }
+        if let Some(parent) = shelf.parent_hashes.first() {
+            println!(
+                "\nCheck out {}, the snapshot the shelf was pushed from, to apply it without conflicts.",
+                &parent[..7]
+            );
+        }
+        return Err(anyhow::anyhow!("Shelf {} conflicts with HEAD. Workspace unchanged.", id));
+    }
+
+    let merged_tree = merge_result
+        .tree_hash
+        .ok_or_else(|| anyhow::anyhow!("Merge failed to produce a tree hash."))?;
+
+    if merged_tree == head_tree {
+        println!("{} Nothing to apply: HEAD already has the shelved changes.", "✔".green());
+        return Ok(());
+    }
+
+    Hydrator::new(&repo.object_store).apply_diff(&head_tree, &merged_tree, &repo.root_path)?;
+
+    let changes = TreeReader::new(&repo.object_store).diff(Some(&head_tree), Some(&merged_tree))?;
+    print_preview_changes("Applied:", &changes);
+
+    println!(
+        "\n{} Applied shelf {}. It was kept; drop it with 'jogen shelve drop {}'.",
+        "✔".green(),
+        id,
+        id
+    );
+    Ok(())
+}
+
+pub fn drop(id: Option<usize>) -> Result<()> {
+    let repo = JogenRepo::from_cwd()?;
+    let (id, shelf_hash) = find_shelf(&repo, id)?;
+
+    repo.ref_store.drop_shelf(id)?;
+    println!("{} Dropped shelf {} ({})", "✔".green(), id, &shelf_hash[..7]);
+    Ok(())
+}
+
+/// Resolves a shelf id, or the newest shelf when none is given, to its snapshot hash.
+fn find_shelf(repo: &JogenRepo, id: Option<usize>) -> Result<(usize, String)> {
+    match id {
+        Some(id) => repo
+            .ref_store
+            .resolve_shelf(id)?
+            .map(|hash| (id, hash))
+            .ok_or_else(|| anyhow::anyhow!("Shelf {} does not exist. See 'jogen shelve list'.", id)),
+        None => repo
+            .ref_store
+            .list_shelves()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No shelves.")),
+    }
+}
@@ -8,12 +8,12 @@ use std::path::PathBuf;
 use crate::commands::JogenRepo;
 
 use jogen_core::{
+    commit_graph::CommitGraph,
     indexer::Indexer,
     object_store::ObjectType,
     objects::{
-        directory::Directory,
+        directory::{Directory, EntryMode},
         snapshot::{Snapshot, SnapshotContext},
-        JogenObject,
     },
 };
 
@@ -71,10 +71,15 @@ pub fn read_directory(hash: String) -> Result<()> {
     let directory = Directory::parse(&content)?;
 
     for entry in directory.entries() {
+        let kind = if entry.mode == EntryMode::Directory {
+            ObjectType::Directory
+        } else {
+            ObjectType::Blob
+        };
         println!(
             "{} {} {}    {}",
             format!("{:06o}", entry.mode as u8).dimmed(),
-            "blob",
+            kind,
             entry.hash.yellow(),
             entry.name
         );
@@ -103,9 +108,7 @@ pub fn write_snapshot() -> Result<()> {
         "Snapshot created via plumbing command".to_string(),
     );
 
-    let snapshot_hash = repo
-        .object_store
-        .write_object(snapshot.serialize()?.as_ref(), ObjectType::Snapshot)?;
+    let snapshot_hash = repo.write_snapshot(&snapshot)?;
 
     println!("Snapshot Hash:  {}", snapshot_hash.green().bold());
     println!("\nTo verify: jogen cat-file {}", snapshot_hash);
@@ -130,94 +133,44 @@ pub fn read_snapshot(hash: String) -> Result<()> {
 
     println!("Snapshot Hash:   {}", hash.green().bold());
     println!("Directory Hash:  {}", snapshot.directory_hash.yellow());
-    println!(
-        "Context:         {}",
-        format!("{:?}", snapshot.context).yellow()
-    );
+    println!("Context:         {}", snapshot.context.as_str().yellow());
     println!("Author:          {}", snapshot.author.yellow());
     println!(
         "Timestamp:       {}",
         snapshot.timestamp.to_string().yellow()
     );
+    for (key, value) in &snapshot.headers {
+        println!("{:<17}{}", format!("{}:", key), value.yellow());
+    }
     println!("\nMessage:\n{}", snapshot.message);
 
     Ok(())
 }
 
-pub fn log(expand: bool) -> Result<()> {
+pub fn write_graph() -> Result<()> {
     let repo = JogenRepo::from_cwd()?;
 
-    let head_hash = {
-        let ref_store = jogen_core::ref_store::RefStore::new(repo.root_path.clone());
-        ref_store
-            .read_head()?
-            .ok_or_else(|| anyhow::anyhow!("No snapshots found (head is empty)"))?
-    };
-
-    if expand {
-        let mut queue = std::collections::VecDeque::new();
-        let mut visited = std::collections::HashSet::new();
-
-        queue.push_back(head_hash.clone());
-        visited.insert(head_hash);
-
-        while let Some(current_hash) = queue.pop_front() {
-            let (kind, content) = repo.object_store.read_object(&current_hash)?;
-
-            if kind != ObjectType::Snapshot {
-                continue;
-            }
-
-            let snapshot = Snapshot::deserialize(&content)?;
-
-            println!("{} {}", "Snapshot:".dimmed(), current_hash.green().bold());
-            println!("Author:    {}", snapshot.author.yellow());
-            println!("Timestamp: {}", snapshot.timestamp.to_string().yellow());
-            println!("Context:   {}", format!("{:?}", snapshot.context).yellow());
-            
-            if snapshot.parent_hashes.len() > 1 {
-                println!("Merge:     {}", snapshot.parent_hashes.join(", ").dimmed());
-            }
-
-            println!("Message:   {}", snapshot.message);
-            println!();
-
-            for parent in snapshot.parent_hashes {
-                if visited.insert(parent.clone()) {
-                    queue.push_back(parent);
-                }
-            }
-        }
-    } else {
-        let mut current_hash = head_hash;
-
-        while !current_hash.is_empty() {
-            let (kind, content) = repo.object_store.read_object(&current_hash)?;
-
-            if kind != ObjectType::Snapshot {
-                return Err(anyhow::anyhow!(
-                    "Object {} is a {}, not a snapshot",
-                    current_hash,
-                    kind
-                ));
-            }
-
-            let snapshot = Snapshot::deserialize(&content)?;
-
-            println!("{} {}", "Snapshot:".dimmed(), current_hash.green().bold());
-            println!("Author:    {}", snapshot.author.yellow());
-            println!("Timestamp: {}", snapshot.timestamp.to_string().yellow());
-            println!("Context:   {}", format!("{:?}", snapshot.context).yellow());
-            println!("Message:   {}", snapshot.message);
-            println!();
-
-            if snapshot.parent_hashes.is_empty() {
-                break;
-            } else {
-                current_hash = snapshot.parent_hashes[0].clone();
-            }
-        }
+    let mut commit_graph = CommitGraph::load(&repo.root_path)?;
+    commit_graph.clear()?;
+
+    let mut tips = Vec::new();
+    tips.extend(repo.ref_store.read_head()?);
+    for track in repo.ref_store.list_tracks()? {
+        tips.extend(repo.ref_store.resolve_track(&track)?);
+    }
+    for label in repo.ref_store.list_labels()? {
+        tips.extend(repo.ref_store.resolve_label(&label)?);
+    }
+
+    for tip in tips {
+        commit_graph.ensure(&repo.object_store, &tip)?;
     }
 
+    println!(
+        "{} Commit graph rebuilt with {} snapshots",
+        "✔".green(),
+        commit_graph.len().to_string().yellow()
+    );
+
     Ok(())
 }
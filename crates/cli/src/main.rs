@@ -2,8 +2,12 @@ mod args;
 mod commands;
 
 use anyhow::Result;
-use args::{Cli, Commands, ToolSubcommands, TrackSubcommands};
+use args::{
+    BisectSubcommands, Cli, Commands, ContextSubcommands, LabelSubcommands, ShelveSubcommands, ToolSubcommands,
+    TrackSubcommands,
+};
 use clap::Parser;
+use jogen_core::ref_store::BisectVerdict;
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -18,18 +22,81 @@ fn main() -> Result<()> {
         Commands::Status => {
             commands::actions::status()?;
         }
-        Commands::Log { expand } => {
-            commands::tools::log(expand)?;
+        Commands::Log(args) => {
+            commands::history::log(args)?;
         }
-        Commands::Checkout { target } => {
-            commands::actions::checkout(target)?;
+        Commands::Changelog(args) => {
+            commands::history::changelog(args)?;
         }
-        Commands::Diff { file } => {
-            commands::actions::diff(file)?;
+        Commands::Blame(args) => {
+            commands::history::blame(args)?;
         }
+        Commands::History(args) => {
+            commands::history::symbol_history(args)?;
+        }
+        Commands::Symbols(args) => {
+            commands::search::symbols(args)?;
+        }
+        Commands::Grep(args) => {
+            commands::search::grep(args)?;
+        }
+        Commands::Checkout(args) => {
+            commands::actions::checkout(args)?;
+        }
+        Commands::Restore(args) => {
+            commands::actions::restore(args)?;
+        }
+        Commands::Diff(args) => match args.semantic {
+            Some(revisions) => {
+                commands::history::semantic_diff(revisions, args.paths)?;
+            }
+            None => {
+                commands::actions::diff(args.paths)?;
+            }
+        },
         Commands::Integrate(args) => {
             commands::actions::integrate(args)?;
         }
+        Commands::Port(args) => {
+            commands::apply::port(args)?;
+        }
+        Commands::Revert(args) => {
+            commands::apply::revert(args)?;
+        }
+        Commands::Bisect(args) => match args.command {
+            BisectSubcommands::Start { bad, good } => {
+                commands::bisect::start(bad, good)?;
+            }
+            BisectSubcommands::Good { revision } => {
+                commands::bisect::mark(BisectVerdict::Good, revision)?;
+            }
+            BisectSubcommands::Bad { revision } => {
+                commands::bisect::mark(BisectVerdict::Bad, revision)?;
+            }
+            BisectSubcommands::Skip { revision } => {
+                commands::bisect::mark(BisectVerdict::Skip, revision)?;
+            }
+            BisectSubcommands::Run { command } => {
+                commands::bisect::run(command)?;
+            }
+            BisectSubcommands::Reset => {
+                commands::bisect::reset()?;
+            }
+        },
+        Commands::Shelve(args) => match args.command {
+            ShelveSubcommands::Push { message } => {
+                commands::shelve::push(message)?;
+            }
+            ShelveSubcommands::List => {
+                commands::shelve::list()?;
+            }
+            ShelveSubcommands::Apply { id } => {
+                commands::shelve::apply(id)?;
+            }
+            ShelveSubcommands::Drop { id } => {
+                commands::shelve::drop(id)?;
+            }
+        },
         Commands::Track(args) => match args.command {
             TrackSubcommands::List => {
                 commands::actions::list_tracks()?;
@@ -38,6 +105,19 @@ fn main() -> Result<()> {
                 commands::actions::create_track(name, switch)?;
             }
         },
+        Commands::Label(args) => match args.command {
+            LabelSubcommands::List => {
+                commands::actions::list_labels()?;
+            }
+            LabelSubcommands::Create { name, revision } => {
+                commands::actions::create_label(name, revision)?;
+            }
+        },
+        Commands::Context(args) => match args.command {
+            ContextSubcommands::List => {
+                commands::actions::list_contexts()?;
+            }
+        },
         Commands::Tools(args) => match args.command {
             ToolSubcommands::Hash { file } => {
                 commands::tools::hash_object(file)?;
@@ -57,6 +137,9 @@ fn main() -> Result<()> {
             ToolSubcommands::ReadSnapshot { hash } => {
                 commands::tools::read_snapshot(hash)?;
             }
+            ToolSubcommands::WriteGraph => {
+                commands::tools::write_graph()?;
+            }
         },
     }
 
@@ -0,0 +1,13 @@
+(preproc_include path: (_) @name) @block
+(preproc_def) @block
+(preproc_function_def) @block
+(function_definition declarator: (_) @name) @block
+(type_definition declarator: (_) @name) @block
+
+; Definitions only: `struct point *p` is not a block.
+(struct_specifier body: (_)) @block
+(union_specifier body: (_)) @block
+(enum_specifier body: (_)) @block
+
+; File-level prototypes and globals.
+(translation_unit (declaration declarator: (_) @name) @block)
@@ -0,0 +1,21 @@
+(preproc_include path: (_) @name) @block
+(preproc_def) @block
+(preproc_function_def) @block
+(namespace_definition) @block
+(function_definition declarator: (_) @name) @block
+(type_definition declarator: (_) @name) @block
+(alias_declaration) @block
+
+; Definitions only: `struct point *p` is not a block.
+(class_specifier body: (_)) @block
+(struct_specifier body: (_)) @block
+(union_specifier body: (_)) @block
+(enum_specifier body: (_)) @block
+
+; Method declarations inside a class body.
+(field_declaration_list
+  (field_declaration declarator: (function_declarator) @name) @block)
+
+; File- and namespace-level prototypes and globals.
+(translation_unit (declaration declarator: (_) @name) @block)
+(declaration_list (declaration declarator: (_) @name) @block)
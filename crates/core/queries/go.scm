@@ -0,0 +1,13 @@
+(import_declaration) @block
+(function_declaration) @block
+(method_declaration) @block
+(type_declaration) @block
+
+; Only package-level constants and variables.
+(source_file (const_declaration) @block)
+(source_file (var_declaration) @block)
+
+; Capitalized names are exported from the package.
+((function_declaration name: (identifier) @public) @block (#match? @public "^[A-Z]"))
+((method_declaration name: (field_identifier) @public) @block (#match? @public "^[A-Z]"))
+((type_declaration (type_spec name: (type_identifier) @public)) @block (#match? @public "^[A-Z]"))
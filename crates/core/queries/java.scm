@@ -0,0 +1,18 @@
+(import_declaration) @block
+(class_declaration) @block
+(interface_declaration) @block
+(enum_declaration) @block
+(record_declaration) @block
+(annotation_type_declaration) @block
+(constructor_declaration) @block
+(method_declaration) @block
+(field_declaration declarator: (_) @name) @block
+
+; Declarations with the `public` modifier are part of the public API.
+([(class_declaration (modifiers "public") @public)
+  (interface_declaration (modifiers "public") @public)
+  (enum_declaration (modifiers "public") @public)
+  (record_declaration (modifiers "public") @public)
+  (constructor_declaration (modifiers "public") @public)
+  (method_declaration (modifiers "public") @public)
+  (field_declaration (modifiers "public") @public)] @block)
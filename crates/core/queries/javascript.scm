@@ -0,0 +1,20 @@
+(import_statement) @block
+(function_declaration) @block
+(generator_function_declaration) @block
+(class_declaration) @block
+(method_definition) @block
+(field_definition property: (_) @name) @block
+
+; Only module-level variables: locals inside functions are part of their function.
+(program (lexical_declaration) @block)
+(program (variable_declaration) @block)
+(export_statement declaration: (lexical_declaration) @block)
+(export_statement declaration: (variable_declaration) @block)
+
+; Exported declarations are part of the public API.
+(export_statement
+  declaration: [(function_declaration)
+                (generator_function_declaration)
+                (class_declaration)
+                (lexical_declaration)
+                (variable_declaration)] @block) @public
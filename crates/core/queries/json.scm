@@ -0,0 +1 @@
+(pair key: (_) @name) @block
@@ -0,0 +1,18 @@
+(import_statement) @block
+(import_from_statement) @block
+(function_definition) @block
+(class_definition) @block
+
+; Module-level assignments such as constants.
+(module (expression_statement (assignment left: (identifier) @name) @block))
+
+; Module-level definitions without a leading underscore are part of the public API.
+(module
+  [(function_definition name: (identifier) @public)
+   (class_definition name: (identifier) @public)] @block
+  (#not-match? @public "^_"))
+(module
+  (decorated_definition
+    definition: [(function_definition name: (identifier) @public)
+                 (class_definition name: (identifier) @public)] @block)
+  (#not-match? @public "^_"))
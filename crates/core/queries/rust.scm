@@ -0,0 +1,29 @@
+; Items anywhere in the file, including inside modules, impls and traits.
+(use_declaration) @block
+(mod_item) @block
+(function_item) @block
+(function_signature_item) @block
+(struct_item) @block
+(enum_item) @block
+(union_item) @block
+(trait_item) @block
+(type_item) @block
+(const_item) @block
+(static_item) @block
+(macro_definition) @block
+
+; Named after the implemented type, not the trait or a generic parameter.
+(impl_item type: (_) @name) @block
+
+; Items exported with a plain `pub` are part of the public API.
+([(function_item (visibility_modifier) @public)
+  (function_signature_item (visibility_modifier) @public)
+  (struct_item (visibility_modifier) @public)
+  (enum_item (visibility_modifier) @public)
+  (union_item (visibility_modifier) @public)
+  (trait_item (visibility_modifier) @public)
+  (type_item (visibility_modifier) @public)
+  (const_item (visibility_modifier) @public)
+  (static_item (visibility_modifier) @public)
+  (mod_item (visibility_modifier) @public)] @block
+ (#eq? @public "pub"))
@@ -0,0 +1,7 @@
+(table) @block
+(table_array_element) @block
+
+; Keys of the document and its tables, not of inline tables.
+(document (pair) @block)
+(table (pair) @block)
+(table_array_element (pair) @block)
@@ -0,0 +1,34 @@
+(import_statement) @block
+(function_declaration) @block
+(generator_function_declaration) @block
+(function_signature) @block
+(class_declaration) @block
+(abstract_class_declaration) @block
+(method_definition) @block
+(method_signature) @block
+(abstract_method_signature) @block
+(public_field_definition name: (_) @name) @block
+(interface_declaration) @block
+(type_alias_declaration) @block
+(enum_declaration) @block
+(internal_module) @block
+
+; Only module-level variables: locals inside functions are part of their function.
+(program (lexical_declaration) @block)
+(program (variable_declaration) @block)
+(export_statement declaration: (lexical_declaration) @block)
+(export_statement declaration: (variable_declaration) @block)
+
+; Exported declarations are part of the public API.
+(export_statement
+  declaration: [(function_declaration)
+                (generator_function_declaration)
+                (function_signature)
+                (class_declaration)
+                (abstract_class_declaration)
+                (interface_declaration)
+                (type_alias_declaration)
+                (enum_declaration)
+                (internal_module)
+                (lexical_declaration)
+                (variable_declaration)] @block) @public
@@ -0,0 +1,146 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::graph::GraphTraversal;
+use crate::{JogenError, Result};
+
+/// What to do next while bisecting.
+#[derive(Debug)]
+pub enum BisectStep {
+    /// Test this snapshot next. `candidates` snapshots could still be the first bad one,
+    /// which takes about `steps` more tests to narrow down.
+    Test {
+        hash: String,
+        candidates: usize,
+        steps: u32,
+    },
+    /// The first bad snapshot.
+    Found(String),
+    /// Only skipped snapshots are left to test: the first bad snapshot is one of these,
+    /// listed newest first.
+    Ambiguous(Vec<String>),
+}
+
+/// Binary search over the snapshot graph for the first bad snapshot.
+///
+/// The candidates are the ancestors of the bad snapshot that are not ancestors of any good one.
+/// This works across integrations: a snapshot on an integrated track is a candidate until a
+/// good snapshot that contains it is found. The next snapshot to test is the one that splits
+/// the candidates most evenly, counting for each candidate how many other candidates it
+/// contains: if it is good, all of those are ruled out; if it is bad, only those remain.
+pub struct Bisector<'a> {
+    graph: &'a GraphTraversal<'a>,
+}
+
+impl<'a> Bisector<'a> {
+    pub fn new(graph: &'a GraphTraversal<'a>) -> Self {
+        Self { graph }
+    }
+
+    pub fn next_step(&self, bad: &str, good: &[String], skipped: &[String]) -> Result<BisectStep> {
+        let mut excluded = HashSet::new();
+        for hash in good {
+            excluded.extend(self.graph.ancestors(hash)?);
+        }
+        if excluded.contains(bad) {
+            return Err(JogenError::Bisect(format!(
+                "bad snapshot {} is an ancestor of a good snapshot",
+                &bad[..7.min(bad.len())]
+            )));
+        }
+
+        // Newest first: every candidate comes before its parents.
+        let candidates: Vec<String> = self
+            .graph
+            .topological_order(bad)?
+            .into_iter()
+            .filter(|hash| !excluded.contains(hash))
+            .collect();
+
+        let skipped: HashSet<&str> = skipped.iter().map(String::as_str).collect();
+        let testable = candidates
+            .iter()
+            .any(|hash| hash != bad && !skipped.contains(hash.as_str()));
+
+        if !testable {
+            if candidates.iter().any(|hash| skipped.contains(hash.as_str())) {
+                return Ok(BisectStep::Ambiguous(candidates));
+            }
+            return Ok(BisectStep::Found(bad.to_string()));
+        }
+
+        let total = candidates.len();
+        let hash = self.best_candidate(&candidates, bad, &skipped)?;
+
+        Ok(BisectStep::Test {
+            hash,
+            candidates: total,
+            steps: usize::BITS - total.leading_zeros() - 1,
+        })
+    }
+
+    /// Finds the testable candidate whose weight, the number of candidates it contains (itself
+    /// included), is closest to half of them. Weights are computed oldest first and the search
+    /// stops at the first candidate that splits the candidates exactly.
+    fn best_candidate(&self, candidates: &[String], bad: &str, skipped: &HashSet<&str>) -> Result<String> {
+        let index: HashMap<&str, usize> = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, hash)| (hash.as_str(), i))
+            .collect();
+        let total = candidates.len();
+
+        // Parents of each candidate that are candidates too.
+        let mut parents: Vec<Vec<usize>> = Vec::with_capacity(total);
+        for hash in candidates {
+            parents.push(
+                self.graph
+                    .node(hash)?
+                    .parents
+                    .iter()
+                    .filter_map(|parent| index.get(parent.as_str()).copied())
+                    .collect(),
+            );
+        }
+
+        let mut weights = vec![0; total];
+        let mut best: Option<(usize, usize)> = None;
+
+        // Parents come later in the order, so walking backwards visits them first.
+        for i in (0..total).rev() {
+            weights[i] = match parents[i].as_slice() {
+                [] => 1,
+                // A snapshot with one candidate parent contains exactly what the parent does.
+                [parent] => weights[*parent] + 1,
+                _ => self.count_ancestors(i, &parents),
+            };
+
+            let hash = candidates[i].as_str();
+            if hash == bad || skipped.contains(hash) {
+                continue;
+            }
+            let score = weights[i].min(total - weights[i]);
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((i, score));
+            }
+            if weights[i].abs_diff(total - weights[i]) <= 1 {
+                break;
+            }
+        }
+
+        Ok(best.map(|(i, _)| candidates[i].clone()).unwrap_or_default())
+    }
+
+    /// Counts the candidates reachable from candidate `start`, itself included.
+    fn count_ancestors(&self, start: usize, parents: &[Vec<usize>]) -> usize {
+        let mut seen = HashSet::from([start]);
+        let mut queue = vec![start];
+        while let Some(i) = queue.pop() {
+            for &parent in &parents[i] {
+                if seen.insert(parent) {
+                    queue.push(parent);
+                }
+            }
+        }
+        seen.len()
+    }
+}
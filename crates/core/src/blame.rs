@@ -0,0 +1,184 @@
+use std::collections::{BinaryHeap, HashMap};
+
+use similar::{DiffOp, TextDiff};
+
+use crate::graph::GraphTraversal;
+use crate::object_store::{ObjectStore, ObjectType};
+use crate::objects::blob::Blob;
+use crate::objects::directory::EntryMode;
+use crate::objects::snapshot::Snapshot;
+use crate::tree::TreeReader;
+use crate::{JogenError, Result};
+
+/// A line of the blamed file and the snapshot that introduced it.
+#[derive(Debug, Clone)]
+pub struct BlameLine {
+    pub snapshot: String,
+    /// 1-based line number in the version of the file recorded by `snapshot`.
+    pub original_line: usize,
+    pub content: String,
+}
+
+/// Attributes every line of a file to the snapshot that last changed it.
+pub struct Blamer<'a> {
+    store: &'a ObjectStore,
+    graph: &'a GraphTraversal<'a>,
+    /// Blob hash of the blamed file in each visited snapshot, None if absent or not a file.
+    versions: HashMap<String, Option<String>>,
+}
+
+impl<'a> Blamer<'a> {
+    pub fn new(store: &'a ObjectStore, graph: &'a GraphTraversal<'a>) -> Self {
+        Self {
+            store,
+            graph,
+            versions: HashMap::new(),
+        }
+    }
+
+    /// Blames `path` as recorded by `head`.
+    ///
+    /// Each snapshot's version of the file is diffed against its first parent's: lines the parent
+    /// already had are passed down to it, the others are attributed to the snapshot. With
+    /// `follow_merges`, lines an integration snapshot did not get from its first parent are looked
+    /// up in its other parents too, so they are attributed to the snapshot that wrote them on the
+    /// integrated track instead of to the integration itself.
+    pub fn blame(&mut self, head: &str, path: &str, follow_merges: bool) -> Result<Vec<BlameLine>> {
+        let head_blob = self
+            .version(head, path)?
+            .ok_or_else(|| JogenError::PathNotFound(path.to_string()))?;
+        let head_text = self.load_text(&head_blob)?.ok_or_else(|| JogenError::BinaryFile(path.to_string()))?;
+
+        let lines: Vec<String> = head_text.lines().map(str::to_string).collect();
+        let mut attributed: Vec<Option<(String, usize)>> = vec![None; lines.len()];
+
+        // Lines still to attribute in each snapshot, as (index in that version, index in head).
+        // Snapshots are visited from the highest generation down, so all children of a snapshot
+        // have handed over their lines before it is processed.
+        let mut pending: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+
+        pending.insert(head.to_string(), (0..lines.len()).map(|i| (i, i)).collect());
+        queue.push((self.graph.node(head)?.generation, head.to_string()));
+
+        while let Some((_, current)) = queue.pop() {
+            let Some(mut remaining) = pending.remove(&current) else {
+                continue;
+            };
+
+            let blob = self.version(&current, path)?.unwrap_or_default();
+            let mut parents = self.graph.node(&current)?.parents;
+            if !follow_merges {
+                parents.truncate(1);
+            }
+
+            for parent in parents {
+                if remaining.is_empty() {
+                    break;
+                }
+                let Some(parent_blob) = self.version(&parent, path)? else {
+                    continue;
+                };
+
+                let unchanged: HashMap<usize, usize> = if parent_blob == blob {
+                    remaining.iter().map(|&(index, _)| (index, index)).collect()
+                } else {
+                    let (Some(old), Some(new)) = (self.load_text(&parent_blob)?, self.load_text(&blob)?) else {
+                        continue;
+                    };
+                    Self::unchanged_lines(&old, &new)
+                };
+
+                let mut passed = Vec::new();
+                remaining.retain(|&(index, head_index)| match unchanged.get(&index) {
+                    Some(&old_index) => {
+                        passed.push((old_index, head_index));
+                        false
+                    }
+                    None => true,
+                });
+
+                if !passed.is_empty() {
+                    let generation = self.graph.node(&parent)?.generation;
+                    pending.entry(parent.clone()).or_default().extend(passed);
+                    queue.push((generation, parent));
+                }
+            }
+
+            for (index, head_index) in remaining {
+                attributed[head_index] = Some((current.clone(), index + 1));
+            }
+        }
+
+        Ok(lines
+            .into_iter()
+            .zip(attributed)
+            .map(|(content, origin)| {
+                let (snapshot, original_line) = origin.unwrap_or_else(|| (head.to_string(), 0));
+                BlameLine {
+                    snapshot,
+                    original_line,
+                    content,
+                }
+            })
+            .collect())
+    }
+
+    /// Maps line indices of `new` that are unchanged from `old` to their index in `old`.
+    fn unchanged_lines(old: &str, new: &str) -> HashMap<usize, usize> {
+        let diff = TextDiff::from_lines(old, new);
+        let mut unchanged = HashMap::new();
+        for op in diff.ops() {
+            if let DiffOp::Equal {
+                old_index,
+                new_index,
+                len,
+            } = *op
+            {
+                for offset in 0..len {
+                    unchanged.insert(new_index + offset, old_index + offset);
+                }
+            }
+        }
+        unchanged
+    }
+
+    /// Returns the blob hash of `path` in a snapshot, or None if it is not a file there.
+    fn version(&mut self, snapshot_hash: &str, path: &str) -> Result<Option<String>> {
+        if let Some(version) = self.versions.get(snapshot_hash) {
+            return Ok(version.clone());
+        }
+
+        let (kind, content) = self.store.read_object(snapshot_hash)?;
+        if kind != ObjectType::Snapshot {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Snapshot, found {}",
+                kind
+            )));
+        }
+        let snapshot = Snapshot::deserialize(&content)?;
+
+        let version = TreeReader::new(self.store)
+            .lookup(&snapshot.directory_hash, path)?
+            .filter(|entry| entry.mode != EntryMode::Directory)
+            .map(|entry| entry.hash);
+
+        self.versions.insert(snapshot_hash.to_string(), version.clone());
+        Ok(version)
+    }
+
+    /// Loads a blob as text, or None if it is binary.
+    fn load_text(&self, blob_hash: &str) -> Result<Option<String>> {
+        let (kind, content) = self.store.read_object(blob_hash)?;
+        if kind != ObjectType::Blob {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Blob, found {}",
+                kind
+            )));
+        }
+        if Blob::is_binary(&content) {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&content).into_owned()))
+    }
+}
@@ -0,0 +1,419 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use tempfile::NamedTempFile;
+
+use crate::object_store::{ObjectStore, ObjectType};
+use crate::objects::snapshot::Snapshot;
+use crate::{JogenError, Result};
+
+const COMMIT_GRAPH_MAGIC: &[u8; 4] = b"JGCG";
+const COMMIT_GRAPH_VERSION: u8 = 2;
+const HASH_SIZE: usize = 32;
+/// Magic, version, base record count and 257 fanout offsets.
+const HEADER_SIZE: usize = 4 + 1 + 4 + 257 * 8;
+/// Fixed part of a record: hash, timestamp, generation and parent count.
+const RECORD_FIXED_SIZE: usize = HASH_SIZE + 8 + 4 + 1;
+/// Number of appended records after which the file is rewritten with all records sorted.
+const TAIL_COMPACT_THRESHOLD: usize = 1024;
+
+/// Ancestry metadata for a single snapshot.
+#[derive(Debug, Clone)]
+pub struct CommitNode {
+    pub parents: Vec<String>,
+    pub timestamp: i64,
+    /// 1 for root snapshots, otherwise one more than the highest parent generation.
+    /// A snapshot always has a strictly higher generation than any of its ancestors.
+    pub generation: u32,
+}
+
+/// A cache of snapshot ancestry stored in `.jogen/commit-graph`.
+///
+/// History queries read parents, timestamps and generation numbers from here instead of
+/// decompressing full snapshot objects. Snapshots missing from the file are loaded from the
+/// object store once and added, so older repositories fill the cache as they are used.
+///
+/// File layout: the magic `JGCG`, a version byte, the number of base records as a
+/// little-endian `u32`, and a fanout table of 257 little-endian `u64` file offsets. Base
+/// records follow, sorted by hash; entry `b` of the fanout is the offset of the first base
+/// record whose hash starts with byte `b`, and entry 256 is the end of the base. Records
+/// appended since the last rewrite follow the base in any order. Each record is:
+///
+/// | Bytes | Field |
+/// | --- | --- |
+/// | 32 | snapshot hash |
+/// | 8 | timestamp, little-endian `i64` |
+/// | 4 | generation, little-endian `u32` |
+/// | 1 | parent count |
+/// | 32 each | parent hashes |
+///
+/// Loading reads the header and the appended records only. A lookup reads the base records
+/// sharing the hash's first byte, once. When more than `TAIL_COMPACT_THRESHOLD` records have
+/// been appended, the file is rewritten with every record in the sorted base.
+pub struct CommitGraph {
+    path: PathBuf,
+    /// Records known so far: the appended ones and those of the base buckets read.
+    nodes: HashMap<String, CommitNode>,
+    fanout: [u64; 257],
+    loaded_buckets: [bool; 256],
+    base_count: usize,
+    tail_count: usize,
+}
+
+impl CommitGraph {
+    pub fn load(root_path: &Path) -> Result<Self> {
+        let path = root_path.join(".jogen/commit-graph");
+        let mut graph = Self::empty(path);
+
+        if !graph.path.exists() {
+            return Ok(graph);
+        }
+
+        let mut file = File::open(&graph.path).map_err(JogenError::Io)?;
+        let mut header = vec![0; HEADER_SIZE];
+        let valid = file.read_exact(&mut header).is_ok()
+            && &header[..4] == COMMIT_GRAPH_MAGIC
+            && header[4] == COMMIT_GRAPH_VERSION;
+        if !valid {
+            // The file is only a cache: start over rather than failing every history query.
+            // Files written by older versions are rebuilt this way too.
+            drop(file);
+            fs::remove_file(&graph.path).map_err(JogenError::Io)?;
+            return Ok(graph);
+        }
+
+        graph.base_count = u32::from_le_bytes(header[5..9].try_into().expect("4 bytes")) as usize;
+        for (i, offset) in header[9..].chunks(8).enumerate() {
+            graph.fanout[i] = u64::from_le_bytes(offset.try_into().expect("8 bytes"));
+        }
+
+        let mut tail = Vec::new();
+        file.seek(SeekFrom::Start(graph.fanout[256])).map_err(JogenError::Io)?;
+        file.read_to_end(&mut tail).map_err(JogenError::Io)?;
+        drop(file);
+
+        let mut cursor = 0;
+        while cursor < tail.len() {
+            match Self::parse_record(&tail[cursor..]) {
+                Some((hash, node, size)) => {
+                    graph.nodes.insert(hash, node);
+                    graph.tail_count += 1;
+                    cursor += size;
+                }
+                None => {
+                    // A partially written trailing record (e.g. after a crash). Drop it so
+                    // that later appends stay aligned.
+                    let file = OpenOptions::new()
+                        .write(true)
+                        .open(&graph.path)
+                        .map_err(JogenError::Io)?;
+                    file.set_len(graph.fanout[256] + cursor as u64).map_err(JogenError::Io)?;
+                    break;
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+
+    fn empty(path: PathBuf) -> Self {
+        Self {
+            path,
+            nodes: HashMap::new(),
+            fanout: [HEADER_SIZE as u64; 257],
+            loaded_buckets: [false; 256],
+            base_count: 0,
+            tail_count: 0,
+        }
+    }
+
+    /// Returns the cached node for `hash`, without loading anything from the object store.
+    pub fn get(&mut self, hash: &str) -> Result<Option<CommitNode>> {
+        if let Some(node) = self.nodes.get(hash) {
+            return Ok(Some(node.clone()));
+        }
+
+        let Some(bucket) = hash.get(..2).and_then(|prefix| u8::from_str_radix(prefix, 16).ok()) else {
+            return Ok(None);
+        };
+        self.load_bucket(bucket as usize)?;
+        Ok(self.nodes.get(hash).cloned())
+    }
+
+    pub fn len(&self) -> usize {
+        self.base_count + self.tail_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Records a newly written snapshot. Parents missing from the graph are loaded first.
+    pub fn add_snapshot(
+        &mut self,
+        store: &ObjectStore,
+        hash: &str,
+        snapshot: &Snapshot,
+    ) -> Result<CommitNode> {
+        if let Some(node) = self.get(hash)? {
+            return Ok(node);
+        }
+
+        let mut generation = 0;
+        for parent in &snapshot.parent_hashes {
+            generation = generation.max(self.ensure(store, parent)?.generation);
+        }
+
+        let node = CommitNode {
+            parents: snapshot.parent_hashes.clone(),
+            timestamp: snapshot.timestamp,
+            generation: generation + 1,
+        };
+        self.nodes.insert(hash.to_string(), node.clone());
+        self.persist(&[(hash.to_string(), node.clone())])?;
+
+        Ok(node)
+    }
+
+    /// Returns the node for `hash`, loading it and any missing ancestors from the object store.
+    pub fn ensure(&mut self, store: &ObjectStore, hash: &str) -> Result<CommitNode> {
+        if let Some(node) = self.get(hash)? {
+            return Ok(node);
+        }
+
+        // Iterative post-order walk: a node is recorded once all of its parents are.
+        let mut loaded: HashMap<String, Snapshot> = HashMap::new();
+        let mut new_nodes = Vec::new();
+        let mut stack = vec![hash.to_string()];
+
+        while let Some(current) = stack.last().cloned() {
+            if self.get(&current)?.is_some() {
+                stack.pop();
+                continue;
+            }
+
+            if !loaded.contains_key(&current) {
+                loaded.insert(current.clone(), Self::load_snapshot(store, &current)?);
+            }
+            let parents = loaded[&current].parent_hashes.clone();
+
+            let mut missing = Vec::new();
+            let mut generation = 0;
+            for parent in &parents {
+                match self.get(parent)? {
+                    Some(node) => generation = generation.max(node.generation),
+                    None => missing.push(parent.clone()),
+                }
+            }
+
+            if missing.is_empty() {
+                let node = CommitNode {
+                    parents,
+                    timestamp: loaded[&current].timestamp,
+                    generation: generation + 1,
+                };
+                self.nodes.insert(current.clone(), node.clone());
+                new_nodes.push((current, node));
+                stack.pop();
+            } else {
+                stack.extend(missing);
+            }
+        }
+
+        self.persist(&new_nodes)?;
+
+        Ok(self.nodes[hash].clone())
+    }
+
+    /// Discards the cached file so it is rebuilt from the object store on the next query.
+    pub fn clear(&mut self) -> Result<()> {
+        *self = Self::empty(self.path.clone());
+        if self.path.exists() {
+            fs::remove_file(&self.path).map_err(JogenError::Io)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the base records whose hash starts with `bucket`, unless they were read already.
+    fn load_bucket(&mut self, bucket: usize) -> Result<()> {
+        if self.loaded_buckets[bucket] {
+            return Ok(());
+        }
+        self.loaded_buckets[bucket] = true;
+
+        let (start, end) = (self.fanout[bucket], self.fanout[bucket + 1]);
+        if start >= end {
+            return Ok(());
+        }
+
+        let mut data = vec![0; (end - start) as usize];
+        let mut file = File::open(&self.path).map_err(JogenError::Io)?;
+        file.seek(SeekFrom::Start(start)).map_err(JogenError::Io)?;
+        file.read_exact(&mut data).map_err(JogenError::Io)?;
+
+        let mut cursor = 0;
+        while cursor < data.len() {
+            let (hash, node, size) = Self::parse_record(&data[cursor..])
+                .ok_or_else(|| JogenError::ObjectCorrupt("Truncated commit-graph record".to_string()))?;
+            self.nodes.entry(hash).or_insert(node);
+            cursor += size;
+        }
+        Ok(())
+    }
+
+    /// Appends records to the file, then rewrites it sorted if too many were appended.
+    fn persist(&mut self, records: &[(String, CommitNode)]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(JogenError::Io)?;
+        }
+
+        let is_new = !self.path.exists();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(JogenError::Io)?;
+        let mut writer = BufWriter::new(file);
+
+        if is_new {
+            writer.write_all(&self.header()).map_err(JogenError::Io)?;
+        }
+
+        for (hash, node) in records {
+            writer.write_all(&Self::encode_record(hash, node)?).map_err(JogenError::Io)?;
+        }
+
+        writer.flush().map_err(JogenError::Io)?;
+        self.tail_count += records.len();
+
+        if self.tail_count > TAIL_COMPACT_THRESHOLD {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites the file with every record in the sorted base.
+    fn compact(&mut self) -> Result<()> {
+        for bucket in 0..256 {
+            self.load_bucket(bucket)?;
+        }
+
+        let mut hashes: Vec<&String> = self.nodes.keys().collect();
+        hashes.sort();
+
+        let mut body = Vec::new();
+        let mut fanout = [0u64; 257];
+        let mut next_bucket = 0;
+        for hash in hashes {
+            let bucket = u8::from_str_radix(&hash[..2], 16)
+                .map_err(|_| JogenError::ObjectCorrupt(format!("Invalid snapshot hash: {}", hash)))?
+                as usize;
+            while next_bucket <= bucket {
+                fanout[next_bucket] = (HEADER_SIZE + body.len()) as u64;
+                next_bucket += 1;
+            }
+            body.extend_from_slice(&Self::encode_record(hash, &self.nodes[hash])?);
+        }
+        while next_bucket <= 256 {
+            fanout[next_bucket] = (HEADER_SIZE + body.len()) as u64;
+            next_bucket += 1;
+        }
+
+        self.fanout = fanout;
+        self.base_count = self.nodes.len();
+        self.tail_count = 0;
+
+        let dir_path = self.path.parent().unwrap_or(Path::new("."));
+        let mut file = NamedTempFile::new_in(dir_path).map_err(JogenError::Io)?;
+        file.write_all(&self.header()).map_err(JogenError::Io)?;
+        file.write_all(&body).map_err(JogenError::Io)?;
+        file.persist(&self.path).map_err(|e| JogenError::Io(e.error))?;
+        Ok(())
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(COMMIT_GRAPH_MAGIC);
+        header.push(COMMIT_GRAPH_VERSION);
+        header.extend_from_slice(&(self.base_count as u32).to_le_bytes());
+        for offset in self.fanout {
+            header.extend_from_slice(&offset.to_le_bytes());
+        }
+        header
+    }
+
+    fn encode_record(hash: &str, node: &CommitNode) -> Result<Vec<u8>> {
+        let decode = |h: &str| {
+            hex::decode(h)
+                .ok()
+                .filter(|b| b.len() == HASH_SIZE)
+                .ok_or_else(|| JogenError::ObjectCorrupt(format!("Invalid snapshot hash: {}", h)))
+        };
+
+        if node.parents.len() > u8::MAX as usize {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Snapshot {} has too many parents for the commit graph",
+                hash
+            )));
+        }
+
+        let mut record = Vec::with_capacity(RECORD_FIXED_SIZE + node.parents.len() * HASH_SIZE);
+        record.extend_from_slice(&decode(hash)?);
+        record.extend_from_slice(&node.timestamp.to_le_bytes());
+        record.extend_from_slice(&node.generation.to_le_bytes());
+        record.push(node.parents.len() as u8);
+        for parent in &node.parents {
+            record.extend_from_slice(&decode(parent)?);
+        }
+        Ok(record)
+    }
+
+    /// Parses one record, returning None if the data is truncated.
+    fn parse_record(data: &[u8]) -> Option<(String, CommitNode, usize)> {
+        if data.len() < RECORD_FIXED_SIZE {
+            return None;
+        }
+
+        let hash = hex::encode(&data[..HASH_SIZE]);
+        let timestamp = i64::from_le_bytes(data[32..40].try_into().ok()?);
+        let generation = u32::from_le_bytes(data[40..44].try_into().ok()?);
+        let parent_count = data[44] as usize;
+
+        let size = RECORD_FIXED_SIZE + parent_count * HASH_SIZE;
+        if data.len() < size {
+            return None;
+        }
+
+        let parents = data[RECORD_FIXED_SIZE..size]
+            .chunks(HASH_SIZE)
+            .map(hex::encode)
+            .collect();
+
+        Some((
+            hash,
+            CommitNode {
+                parents,
+                timestamp,
+                generation,
+            },
+            size,
+        ))
+    }
+
+    fn load_snapshot(store: &ObjectStore, hash: &str) -> Result<Snapshot> {
+        let (kind, content) = store.read_object(hash)?;
+        if kind != ObjectType::Snapshot {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Snapshot, found {}",
+                kind
+            )));
+        }
+        Snapshot::deserialize(&content)
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::objects::snapshot::{Snapshot, SnapshotContext};
+use crate::suggest::SuggestConfig;
+use crate::{JogenError, Result};
+
+/// Project configuration stored in `.jogen/config.toml`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Config {
+    pub core: CoreConfig,
+    /// Extra snapshot contexts, and rules for the built-in ones, keyed by context name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub contexts: BTreeMap<String, ContextDefinition>,
+    /// Rules used by `snapshot --suggest-context`.
+    #[serde(default, skip_serializing_if = "SuggestConfig::is_default")]
+    pub suggest: SuggestConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CoreConfig {
+    pub version: u8,
+}
+
+/// A context declared in the configuration, e.g.
+///
+/// ```toml
+/// [contexts.security]
+/// description = "fixes a vulnerability"
+/// requires = ["task"]
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ContextDefinition {
+    #[serde(default)]
+    pub description: String,
+    /// Header names a snapshot with this context must carry, such as `task`.
+    #[serde(default)]
+    pub requires: Vec<String>,
+}
+
+/// A context that can be used for new snapshots, with its description and rules.
+pub struct ContextInfo<'a> {
+    pub context: SnapshotContext,
+    pub description: &'a str,
+    pub requires: &'a [String],
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            core: CoreConfig { version: 1 },
+            contexts: BTreeMap::new(),
+            suggest: SuggestConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads `.jogen/config.toml`. A missing file yields the default configuration.
+    pub fn load(root_path: &Path) -> Result<Self> {
+        let path = root_path.join(".jogen").join("config.toml");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        let config: Self = toml::from_str(&content)?;
+
+        for name in config.contexts.keys() {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return Err(JogenError::InvalidContext(format!(
+                    "'{}' in config.toml: context names may only contain letters, digits, '-' and '_'",
+                    name
+                )));
+            }
+        }
+
+        let contexts = config.contexts();
+        for rule in &config.suggest.rules {
+            let context = SnapshotContext::from(rule.context.as_str());
+            if !contexts.iter().any(|info| info.context == context) {
+                return Err(JogenError::InvalidContext(format!(
+                    "'{}' in a [[suggest.rules]] entry of config.toml is not declared",
+                    rule.context
+                )));
+            }
+        }
+
+        Ok(config)
+    }
+
+    pub fn save(&self, root_path: &Path) -> Result<()> {
+        let config_toml = toml::to_string_pretty(self)?;
+        fs::write(root_path.join(".jogen").join("config.toml"), config_toml)?;
+        Ok(())
+    }
+
+    /// Lists the built-in contexts followed by the declared ones.
+    /// Declarations that reuse a built-in name only add a description or rules to it.
+    pub fn contexts(&self) -> Vec<ContextInfo<'_>> {
+        let mut contexts: Vec<ContextInfo<'_>> = SnapshotContext::BUILT_IN
+            .iter()
+            .map(|context| {
+                let definition = self.contexts.get(context.as_str());
+                ContextInfo {
+                    context: context.clone(),
+                    description: definition
+                        .map(|d| d.description.as_str())
+                        .filter(|d| !d.is_empty())
+                        .unwrap_or_else(|| context.description()),
+                    requires: definition.map(|d| d.requires.as_slice()).unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        for (name, definition) in &self.contexts {
+            let context = SnapshotContext::from(name.as_str());
+            if context.is_built_in() {
+                continue;
+            }
+            contexts.push(ContextInfo {
+                context,
+                description: &definition.description,
+                requires: &definition.requires,
+            });
+        }
+
+        contexts
+    }
+
+    /// Checks that a new snapshot uses a known context and carries the headers its context requires.
+    pub fn validate(&self, snapshot: &Snapshot) -> Result<()> {
+        let contexts = self.contexts();
+        let Some(info) = contexts.iter().find(|info| info.context == snapshot.context) else {
+            let known: Vec<&str> = contexts.iter().map(|info| info.context.as_str()).collect();
+            return Err(JogenError::InvalidContext(format!(
+                "'{}' is not declared. Known contexts: {}. Declare new ones under [contexts] in .jogen/config.toml",
+                snapshot.context,
+                known.join(", ")
+            )));
+        };
+
+        if let Some(missing) = info.requires.iter().find(|field| snapshot.header(field).is_none()) {
+            return Err(JogenError::InvalidContext(format!(
+                "'{}' snapshots require a '{}' header",
+                snapshot.context, missing
+            )));
+        }
+
+        Ok(())
+    }
+}
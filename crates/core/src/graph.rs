@@ -1,79 +1,205 @@
-use std::collections::{HashSet, VecDeque};
+use std::cell::RefCell;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 
-use crate::object_store::{ObjectStore, ObjectType};
-use crate::objects::snapshot::Snapshot;
-use crate::{JogenError, Result};
+use crate::commit_graph::{CommitGraph, CommitNode};
+use crate::object_store::ObjectStore;
+use crate::Result;
+
+/// Paint flags used by the merge-base walk.
+const FROM_A: u8 = 1;
+const FROM_B: u8 = 2;
+const STALE: u8 = 4;
 
 pub struct GraphTraversal<'a> {
     store: &'a ObjectStore,
+    commit_graph: RefCell<CommitGraph>,
 }
 
 impl<'a> GraphTraversal<'a> {
-    pub fn new(store: &'a ObjectStore) -> Self {
-        Self { store }
+    pub fn new(store: &'a ObjectStore, commit_graph: CommitGraph) -> Self {
+        Self {
+            store,
+            commit_graph: RefCell::new(commit_graph),
+        }
     }
 
-    /// Finds the lowest common ancestor (LCA) snapshot hash between two snapshot hashes.
+    /// Finds the best common ancestor between two snapshot hashes.
+    /// When several equally good ancestors exist (criss-cross merges), the most recent one is returned;
+    /// use `merge_bases` to get all of them.
     pub fn find_common_ancestor(&self, head_a: &str, head_b: &str) -> Result<Option<String>> {
+        Ok(self.merge_bases(head_a, head_b)?.into_iter().next())
+    }
+
+    /// Finds every best common ancestor of two snapshots: common ancestors that are not
+    /// themselves ancestors of another common ancestor. Criss-cross histories can have several.
+    /// Results are ordered from newest to oldest.
+    pub fn merge_bases(&self, head_a: &str, head_b: &str) -> Result<Vec<String>> {
         if head_a == head_b {
-            return Ok(Some(head_a.to_string()));
+            return Ok(vec![head_a.to_string()]);
+        }
+
+        // Paint ancestors of A and B, walking in decreasing generation order. Because every
+        // descendant of a snapshot has a higher generation, a snapshot's flags are final when it
+        // is popped: if it is reached from both sides and not below another common ancestor, it
+        // is a best merge base. Everything below a merge base is marked stale.
+        let mut flags: HashMap<String, u8> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        let mut bases = Vec::new();
+        // How many times each snapshot is in the queue, and how many queued entries are not
+        // stale. The walk ends once only stale entries are left.
+        let mut queued: HashMap<String, usize> = HashMap::new();
+        let mut active = 0usize;
+
+        for (hash, flag) in [(head_a, FROM_A), (head_b, FROM_B)] {
+            let node = self.node(hash)?;
+            flags.insert(hash.to_string(), flag);
+            *queued.entry(hash.to_string()).or_default() += 1;
+            active += 1;
+            queue.push((node.generation, node.timestamp, hash.to_string()));
         }
 
-        let mut queue_a = VecDeque::new();
-        let mut queue_b = VecDeque::new();
-        
-        let mut visited_a = HashSet::new();
-        let mut visited_b = HashSet::new();
+        while active > 0 {
+            let Some((_, timestamp, current)) = queue.pop() else {
+                break;
+            };
 
-        queue_a.push_back(head_a.to_string());
-        visited_a.insert(head_a.to_string());
+            let mut current_flags = flags[&current];
+            if let Some(count) = queued.get_mut(&current) {
+                *count -= 1;
+            }
+            if current_flags & STALE == 0 {
+                active -= 1;
+            }
 
-        queue_b.push_back(head_b.to_string());
-        visited_b.insert(head_b.to_string());
+            if current_flags & (FROM_A | FROM_B) == FROM_A | FROM_B && current_flags & STALE == 0 {
+                bases.push((timestamp, current.clone()));
+                current_flags |= STALE;
+                flags.insert(current.clone(), current_flags);
+                active -= queued[&current];
+            }
 
-        while !queue_a.is_empty() || !queue_b.is_empty() {
-            // Process one layer of A
-            if let Some(curr_a) = queue_a.pop_front() {
-                if visited_b.contains(&curr_a) {
-                    return Ok(Some(curr_a));
+            for parent in self.node(&current)?.parents {
+                let parent_flags = flags.get(&parent).copied().unwrap_or(0);
+                if parent_flags & current_flags == current_flags {
+                    continue;
                 }
+                let new_flags = parent_flags | current_flags;
+                flags.insert(parent.clone(), new_flags);
 
-                if let Ok(snapshot) = self.load_snapshot(&curr_a) {
-                    for parent in snapshot.parent_hashes {
-                        if visited_a.insert(parent.clone()) {
-                            queue_a.push_back(parent);
-                        }
-                    }
+                let count = queued.entry(parent.clone()).or_default();
+                if parent_flags & STALE == 0 && new_flags & STALE != 0 {
+                    active -= *count;
                 }
+                *count += 1;
+                if new_flags & STALE == 0 {
+                    active += 1;
+                }
+
+                let parent_node = self.node(&parent)?;
+                queue.push((parent_node.generation, parent_node.timestamp, parent));
             }
+        }
+
+        bases.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+        Ok(bases.into_iter().map(|(_, hash)| hash).collect())
+    }
+
+    /// Collects every snapshot reachable from `head`, including `head` itself.
+    pub fn ancestors(&self, head: &str) -> Result<HashSet<String>> {
+        let mut queue = VecDeque::new();
+        let mut visited = HashSet::new();
+
+        queue.push_back(head.to_string());
+        visited.insert(head.to_string());
 
-            // Process one layer of B
-            if let Some(curr_b) = queue_b.pop_front() {
-                if visited_a.contains(&curr_b) {
-                    return Ok(Some(curr_b));
+        while let Some(current) = queue.pop_front() {
+            for parent in self.node(&current)?.parents {
+                if visited.insert(parent.clone()) {
+                    queue.push_back(parent);
                 }
+            }
+        }
 
-                if let Ok(snapshot) = self.load_snapshot(&curr_b) {
-                    for parent in snapshot.parent_hashes {
-                        if visited_b.insert(parent.clone()) {
-                            queue_b.push_back(parent);
-                        }
-                    }
+        Ok(visited)
+    }
+
+    /// Lists `head` and all of its ancestors in topological order: every snapshot comes before
+    /// its parents. When several snapshots are ready, the most recent one comes first.
+    pub fn topological_order(&self, head: &str) -> Result<Vec<String>> {
+        let ancestors = self.ancestors(head)?;
+
+        let mut child_counts: HashMap<String, usize> = HashMap::new();
+        for hash in &ancestors {
+            for parent in self.node(hash)?.parents {
+                *child_counts.entry(parent).or_default() += 1;
+            }
+        }
+
+        let mut ready = BinaryHeap::new();
+        ready.push((self.node(head)?.timestamp, head.to_string()));
+
+        let mut order = Vec::with_capacity(ancestors.len());
+        while let Some((_, current)) = ready.pop() {
+            for parent in self.node(&current)?.parents {
+                let Some(count) = child_counts.get_mut(&parent) else {
+                    continue;
+                };
+                *count -= 1;
+                if *count == 0 {
+                    let timestamp = self.node(&parent)?.timestamp;
+                    ready.push((timestamp, parent));
                 }
             }
+            order.push(current);
         }
 
-        Ok(None)
+        Ok(order)
     }
 
-    fn load_snapshot(&self, hash: &str) -> Result<Snapshot> {
-        let (kind, content) = self.store.read_object(hash)?;
-        if kind != ObjectType::Snapshot {
-            return Err(JogenError::ObjectCorrupt(format!(
-                "Expected Snapshot, found {}",
-                kind
-            )));
+    /// Lists the snapshots reachable from `to` but not from `from` (the range `from..to`),
+    /// in topological order.
+    pub fn range(&self, from: &str, to: &str) -> Result<Vec<String>> {
+        let excluded = self.ancestors(from)?;
+        Ok(self
+            .topological_order(to)?
+            .into_iter()
+            .filter(|hash| !excluded.contains(hash))
+            .collect())
+    }
+
+    /// Returns true if `ancestor` is reachable from `descendant` by following parent links.
+    /// A snapshot counts as its own ancestor.
+    pub fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool> {
+        let target_generation = self.node(ancestor)?.generation;
+
+        let mut stack = vec![descendant.to_string()];
+        let mut visited = HashSet::new();
+        visited.insert(descendant.to_string());
+
+        while let Some(current) = stack.pop() {
+            if current == ancestor {
+                return Ok(true);
+            }
+
+            let node = self.node(&current)?;
+            // Nothing at or below the ancestor's generation can lead to it.
+            if node.generation <= target_generation {
+                continue;
+            }
+
+            for parent in node.parents {
+                if visited.insert(parent.clone()) {
+                    stack.push(parent);
+                }
+            }
         }
-        Snapshot::deserialize(&content)
+
+        Ok(false)
+    }
+
+    /// Returns the ancestry metadata for a snapshot, loading it into the commit graph if needed.
+    pub fn node(&self, hash: &str) -> Result<CommitNode> {
+        self.commit_graph.borrow_mut().ensure(self.store, hash)
     }
 }
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use rayon::prelude::*;
+use regex::Regex;
+
+use crate::object_store::{ObjectStore, ObjectType};
+use crate::objects::blob::Blob;
+use crate::objects::directory::DirectoryEntry;
+use crate::tree::TreeReader;
+use crate::{JogenError, Result};
+
+/// A line of a file matching a pattern.
+#[derive(Debug, Clone)]
+pub struct GrepMatch {
+    /// `/`-separated path of the file, relative to the tree root.
+    pub path: String,
+    /// 1-based line number.
+    pub line_number: usize,
+    /// The line, without its line ending.
+    pub line: String,
+    /// Byte ranges of the matches within the line.
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// Searches the files recorded in trees of the object store, without touching the workspace.
+pub struct TreeGrep<'a> {
+    store: &'a ObjectStore,
+    pattern: &'a Regex,
+}
+
+impl<'a> TreeGrep<'a> {
+    pub fn new(store: &'a ObjectStore, pattern: &'a Regex) -> Self {
+        Self { store, pattern }
+    }
+
+    /// Lists the matching lines of every file in a tree, sorted by path and line. When `paths` is
+    /// not empty, only files at or under one of them are searched. Binary files are skipped.
+    pub fn search(&self, tree_hash: &str, paths: &[String]) -> Result<Vec<GrepMatch>> {
+        let files: Vec<(String, DirectoryEntry)> = TreeReader::new(self.store)
+            .files(tree_hash)?
+            .into_iter()
+            .filter(|(path, _)| selected(path, paths))
+            .collect();
+
+        let per_file: Vec<Vec<GrepMatch>> = files
+            .par_iter()
+            .map(|(path, entry)| {
+                Ok(match self.read_text(entry)? {
+                    Some(content) => self.search_text(path, &content),
+                    None => Vec::new(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(per_file.into_iter().flatten().collect())
+    }
+
+    /// Lists the matching lines that `new_tree` has and `old_tree` did not, i.e. the matches a
+    /// snapshot introduced. A missing old tree is treated as empty. Lines are compared by their
+    /// text, so a matching line that only moved within a file is not reported.
+    pub fn search_introduced(
+        &self,
+        old_tree: Option<&str>,
+        new_tree: &str,
+        paths: &[String],
+    ) -> Result<Vec<GrepMatch>> {
+        let changes: Vec<_> = TreeReader::new(self.store)
+            .diff(old_tree, Some(new_tree))?
+            .into_iter()
+            .filter(|change| change.new.is_some() && selected(&change.path, paths))
+            .collect();
+
+        let per_file: Vec<Vec<GrepMatch>> = changes
+            .par_iter()
+            .map(|change| {
+                let Some(new) = change.new.as_ref().map(|entry| self.read_text(entry)).transpose()?.flatten()
+                else {
+                    return Ok(Vec::new());
+                };
+                let old = change.old.as_ref().map(|entry| self.read_text(entry)).transpose()?.flatten();
+
+                // Matching lines of the old version, by text, each consumed by one new line.
+                let mut known: HashMap<String, usize> = HashMap::new();
+                if let Some(old) = old {
+                    for found in self.search_text(&change.path, &old) {
+                        *known.entry(found.line).or_default() += 1;
+                    }
+                }
+
+                Ok(self
+                    .search_text(&change.path, &new)
+                    .into_iter()
+                    .filter(|found| match known.get_mut(&found.line) {
+                        Some(count) if *count > 0 => {
+                            *count -= 1;
+                            false
+                        }
+                        _ => true,
+                    })
+                    .collect())
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(per_file.into_iter().flatten().collect())
+    }
+
+    fn search_text(&self, path: &str, content: &str) -> Vec<GrepMatch> {
+        content
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let ranges: Vec<Range<usize>> = self.pattern.find_iter(line).map(|found| found.range()).collect();
+                (!ranges.is_empty()).then(|| GrepMatch {
+                    path: path.to_string(),
+                    line_number: index + 1,
+                    line: line.to_string(),
+                    ranges,
+                })
+            })
+            .collect()
+    }
+
+    /// Reads a file's content, or None if it is binary.
+    fn read_text(&self, entry: &DirectoryEntry) -> Result<Option<String>> {
+        let (kind, content) = self.store.read_object(&entry.hash)?;
+        if kind != ObjectType::Blob {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Blob, found {}",
+                kind
+            )));
+        }
+        if Blob::is_binary(&content) {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&content).into_owned()))
+    }
+}
+
+fn selected(path: &str, paths: &[String]) -> bool {
+    paths.is_empty()
+        || paths.iter().any(|filter| {
+            let filter = filter.trim_end_matches('/');
+            path == filter || path.starts_with(&format!("{}/", filter))
+        })
+}
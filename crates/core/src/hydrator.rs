@@ -5,9 +5,10 @@ use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 
-use crate::merge::{MergeConflict, MergeConflictIncoming};
+use crate::merge::MergeConflict;
 use crate::object_store::ObjectStore;
-use crate::objects::directory::{Directory, EntryMode};
+use crate::objects::directory::{Directory, DirectoryEntry, EntryMode};
+use crate::tree::TreeReader;
 use crate::{JogenError, Result};
 
 pub struct Hydrator<'a> {
@@ -103,6 +104,67 @@ impl<'a> Hydrator<'a> {
         Ok(())
     }
 
+    /// Makes one path of the workspace match its version in `target_tree`, given the tree the
+    /// workspace currently indexes to. Files under a restored directory that the target does not
+    /// have are removed, and a path missing from the target is deleted. An empty path restores
+    /// the whole workspace.
+    pub fn restore_path(
+        &self,
+        current_tree: Option<&str>,
+        target_tree: &str,
+        path: &str,
+        root_path: &Path,
+    ) -> Result<()> {
+        if path.is_empty() {
+            return match current_tree {
+                Some(current_tree) => self.apply_diff(current_tree, target_tree, root_path),
+                None => self.hydrate_directory(target_tree, root_path),
+            };
+        }
+
+        let reader = TreeReader::new(self.store);
+        let current = match current_tree {
+            Some(current_tree) => reader.lookup(current_tree, path)?,
+            None => None,
+        };
+        let target = reader.lookup(target_tree, path)?;
+        let full_path = root_path.join(path);
+
+        match (current, target) {
+            (None, None) => Err(JogenError::PathNotFound(path.to_string())),
+            (Some(current), Some(target))
+                if current.mode == EntryMode::Directory && target.mode == EntryMode::Directory =>
+            {
+                self.apply_diff(&current.hash, &target.hash, &full_path)
+            }
+            (current, target) => {
+                if let Some(current) = &current {
+                    if target.as_ref().is_some_and(|target| {
+                        target.hash == current.hash && target.mode == current.mode
+                    }) {
+                        return Ok(());
+                    }
+                    self.remove_entry(current, &full_path)?;
+                }
+                match target {
+                    Some(target) if target.mode == EntryMode::Directory => {
+                        self.hydrate_directory(&target.hash, &full_path)
+                    }
+                    Some(target) => self.write_blob(&target.hash, &full_path, target.mode),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    fn remove_entry(&self, entry: &DirectoryEntry, path: &Path) -> Result<()> {
+        if entry.mode == EntryMode::Directory {
+            fs::remove_dir_all(path).map_err(JogenError::Io)
+        } else {
+            fs::remove_file(path).map_err(JogenError::Io)
+        }
+    }
+
     /// Recursively writes a directory tree from the object store to the filesystem.
     pub fn hydrate_directory(&self, tree_hash: &str, path: &Path) -> Result<()> {
         let dir = self.load_directory(tree_hash)?;
@@ -164,7 +226,8 @@ impl<'a> Hydrator<'a> {
         incoming_path
     }
 
-    /// Writes incoming versions of conflicted files alongside originals with a .incoming extension.
+    /// Writes incoming versions of conflicted entries alongside originals with a .incoming extension.
+    /// Incoming directories are hydrated as a whole so directory/file conflicts can be compared.
     /// Returns the created conflict marker paths relative to repo root.
     pub fn write_conflict_files(&self, conflicts: &[MergeConflict], root_path: &Path) -> Result<Vec<String>> {
         let mut incoming_paths = Vec::with_capacity(conflicts.len());
@@ -178,19 +241,13 @@ impl<'a> Hydrator<'a> {
             }
 
             match &conflict.incoming {
-                MergeConflictIncoming::BlobHash(incoming_hash) => {
-                    let (kind, content) = self.store.read_object(incoming_hash)?;
-                    if kind == crate::object_store::ObjectType::Blob {
-                        fs::write(&incoming_path, content).map_err(JogenError::Io)?;
-                    } else {
-                        fs::write(
-                            &incoming_path,
-                            b"Incoming side is not a file. Resolve manually, then remove this marker.\n",
-                        )
-                        .map_err(JogenError::Io)?;
-                    }
+                Some(entry) if entry.mode == EntryMode::Directory => {
+                    self.hydrate_directory(&entry.hash, &incoming_path)?;
                 }
-                MergeConflictIncoming::Deleted => {
+                Some(entry) => {
+                    self.write_blob(&entry.hash, &incoming_path, entry.mode)?;
+                }
+                None => {
                     fs::write(
                         &incoming_path,
                         b"Deleted in incoming target. Keep or delete original file, then remove this marker.\n",
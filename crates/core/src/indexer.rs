@@ -1,6 +1,8 @@
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use rayon::prelude::*;
 use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 
 use crate::object_store::ObjectStore;
@@ -68,6 +70,8 @@ impl<'a> Indexer<'a> {
                         let child_meta = entry.metadata().map_err(JogenError::Io)?;
                         let mode = if child_meta.is_dir() {
                             EntryMode::Directory
+                        } else if Self::is_executable(&child_meta) {
+                            EntryMode::Executable
                         } else {
                             EntryMode::File
                         };
@@ -84,10 +88,8 @@ impl<'a> Indexer<'a> {
                 .collect();
 
             let mut directory = Directory::new();
-            for child_opt in child_results? {
-                if let Some(child) = child_opt {
-                    directory.add_entry(child);
-                }
+            for child in child_results?.into_iter().flatten() {
+                directory.add_entry(child);
             }
 
             let hash = self
@@ -111,4 +113,14 @@ impl<'a> Indexer<'a> {
 
         Ok(None)
     }
+
+    #[cfg(unix)]
+    fn is_executable(metadata: &fs::Metadata) -> bool {
+        metadata.permissions().mode() & 0o111 != 0
+    }
+
+    #[cfg(not(unix))]
+    fn is_executable(_metadata: &fs::Metadata) -> bool {
+        false
+    }
 }
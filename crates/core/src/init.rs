@@ -1,18 +1,8 @@
+use crate::config::Config;
 use crate::{JogenError, Result};
-use serde::Serialize;
 use std::fs;
 use std::path::PathBuf;
 
-#[derive(Serialize)]
-struct CoreConfig {
-    version: u8,
-}
-
-#[derive(Serialize)]
-struct ConfigFile {
-    core: CoreConfig,
-}
-
 pub fn execute(target_path: Option<PathBuf>) -> Result<PathBuf> {
     let root = target_path.unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
 
@@ -31,11 +21,7 @@ pub fn execute(target_path: Option<PathBuf>) -> Result<PathBuf> {
         fs::create_dir_all(folder)?;
     }
 
-    let config = ConfigFile {
-        core: CoreConfig { version: 1 },
-    };
-    let config_toml = toml::to_string_pretty(&config)?;
-    fs::write(jogen_dir.join("config.toml"), config_toml)?;
+    Config::default().save(&root)?;
 
     fs::write(jogen_dir.join("HEAD"), "ref: refs/tracks/main\n")?;
 
@@ -0,0 +1,171 @@
+use tree_sitter::Language;
+
+/// Describes how the semantic engine finds blocks in files of one language.
+///
+/// Blocks are selected with a tree-sitter query over the file's syntax tree. Each pattern
+/// captures the block node as `@block` and may capture the node holding its name as `@name`:
+///
+/// ```scheme
+/// (function_item) @block
+/// (impl_item type: (_) @name) @block
+/// (program (lexical_declaration) @block)
+/// ```
+///
+/// Without a `@name` capture, the block's `name` field or its first identifier names it.
+/// Nesting rules are written as patterns on the parent: the last line above only matches
+/// declarations at the top level of a program. A block captured inside another block is
+/// listed with the enclosing block's name in its breadcrumbs. A pattern that also captures
+/// `@public` marks the block as part of the public API, e.g.
+/// `(export_statement declaration: (_) @block) @public`.
+///
+/// The built-in languages implement this trait through [`BuiltinLanguage`]. Other grammars can
+/// be added with `SemanticEngine::register`.
+pub trait LanguageDefinition: Send + Sync {
+    /// Display name, e.g. `Rust`.
+    fn name(&self) -> &str;
+
+    /// File extensions handled by this language, without the leading dot.
+    fn extensions(&self) -> &[&str];
+
+    fn grammar(&self) -> Language;
+
+    /// The tree-sitter query selecting blocks.
+    fn block_query(&self) -> &str;
+
+    /// Node kinds whose text can name a block.
+    fn identifier_kinds(&self) -> &[&str] {
+        IDENTIFIERS
+    }
+}
+
+/// Node kinds whose text names a block, shared by most grammars.
+pub const IDENTIFIERS: &[&str] = &[
+    "identifier",
+    "name",
+    "type_identifier",
+    "field_identifier",
+    "property_identifier",
+];
+
+/// The languages jogen-core ships grammars for.
+#[derive(Debug, Clone, Copy)]
+pub enum BuiltinLanguage {
+    Rust,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Python,
+    Go,
+    C,
+    Cpp,
+    Java,
+    Toml,
+    Json,
+}
+
+impl BuiltinLanguage {
+    pub const ALL: [BuiltinLanguage; 11] = [
+        Self::Rust,
+        Self::JavaScript,
+        Self::TypeScript,
+        Self::Tsx,
+        Self::Python,
+        Self::Go,
+        Self::C,
+        Self::Cpp,
+        Self::Java,
+        Self::Toml,
+        Self::Json,
+    ];
+}
+
+impl LanguageDefinition for BuiltinLanguage {
+    fn name(&self) -> &str {
+        match self {
+            Self::Rust => "Rust",
+            Self::JavaScript => "JavaScript",
+            Self::TypeScript => "TypeScript",
+            Self::Tsx => "TSX",
+            Self::Python => "Python",
+            Self::Go => "Go",
+            Self::C => "C",
+            Self::Cpp => "C++",
+            Self::Java => "Java",
+            Self::Toml => "TOML",
+            Self::Json => "JSON",
+        }
+    }
+
+    fn extensions(&self) -> &[&str] {
+        match self {
+            Self::Rust => &["rs"],
+            Self::JavaScript => &["js", "jsx", "mjs", "cjs"],
+            Self::TypeScript => &["ts", "mts", "cts"],
+            Self::Tsx => &["tsx"],
+            Self::Python => &["py", "pyi"],
+            Self::Go => &["go"],
+            Self::C => &["c", "h"],
+            Self::Cpp => &["cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+            Self::Java => &["java"],
+            Self::Toml => &["toml"],
+            Self::Json => &["json"],
+        }
+    }
+
+    fn grammar(&self) -> Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+            Self::C => tree_sitter_c::LANGUAGE.into(),
+            Self::Cpp => tree_sitter_cpp::LANGUAGE.into(),
+            Self::Java => tree_sitter_java::LANGUAGE.into(),
+            Self::Toml => tree_sitter_toml_ng::LANGUAGE.into(),
+            Self::Json => tree_sitter_json::LANGUAGE.into(),
+        }
+    }
+
+    fn block_query(&self) -> &str {
+        match self {
+            Self::Rust => include_str!("../queries/rust.scm"),
+            Self::JavaScript => include_str!("../queries/javascript.scm"),
+            Self::TypeScript | Self::Tsx => include_str!("../queries/typescript.scm"),
+            Self::Python => include_str!("../queries/python.scm"),
+            Self::Go => include_str!("../queries/go.scm"),
+            Self::C => include_str!("../queries/c.scm"),
+            Self::Cpp => include_str!("../queries/cpp.scm"),
+            Self::Java => include_str!("../queries/java.scm"),
+            Self::Toml => include_str!("../queries/toml.scm"),
+            Self::Json => include_str!("../queries/json.scm"),
+        }
+    }
+
+    fn identifier_kinds(&self) -> &[&str] {
+        match self {
+            Self::Cpp => &[
+                "identifier",
+                "type_identifier",
+                "field_identifier",
+                "namespace_identifier",
+                "destructor_name",
+                "operator_name",
+                "string_content",
+                "system_lib_string",
+            ],
+            Self::C => &["identifier", "type_identifier", "field_identifier", "string_content", "system_lib_string"],
+            Self::Go => &[
+                "identifier",
+                "type_identifier",
+                "field_identifier",
+                "package_identifier",
+                "interpreted_string_literal_content",
+            ],
+            Self::Toml => &["bare_key", "dotted_key", "quoted_key"],
+            Self::Json => &["string_content"],
+            _ => IDENTIFIERS,
+        }
+    }
+}
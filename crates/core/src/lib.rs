@@ -1,12 +1,24 @@
+pub mod bisect;
+pub mod blame;
+pub mod commit_graph;
+pub mod config;
 pub mod graph;
+pub mod grep;
 pub mod hydrator;
 pub mod indexer;
 pub mod init;
+pub mod language;
 pub mod merge;
 pub mod object_store;
 pub mod objects;
 pub mod ref_store;
+pub mod resolution_cache;
+pub mod revision;
 pub mod semantic;
+pub mod semantic_diff;
+pub mod suggest;
+pub mod symbol_index;
+pub mod tree;
 
 use std::path::{Path, PathBuf};
 use thiserror::Error;
@@ -30,12 +42,40 @@ pub enum JogenError {
     #[error("Invalid Entry Mode: {0}")]
     InvalidEntryMode(u8),
 
+    // --- Revision Errors ---
+    #[error("Could not resolve revision: {0}")]
+    RevisionNotFound(String),
+
+    #[error("Revision is ambiguous, more than one snapshot matches: {0}")]
+    AmbiguousRevision(String),
+
+    #[error("Cannot bisect: {0}")]
+    Bisect(String),
+
+    // --- Path Errors ---
+    #[error("Path not found in snapshot: {0}")]
+    PathNotFound(String),
+
+    #[error("File is binary: {0}")]
+    BinaryFile(String),
+
     // --- System Errors ---
     #[error("Input/Output Error: {0}")]
     Io(#[from] std::io::Error),
 
     #[error("Configuration Error: {0}")]
     Config(#[from] toml::ser::Error),
+
+    #[error("Configuration Error: {0}")]
+    ConfigParse(#[from] toml::de::Error),
+
+    // --- Snapshot Errors ---
+    #[error("Invalid context: {0}")]
+    InvalidContext(String),
+
+    // --- Semantic Errors ---
+    #[error("Invalid language definition: {0}")]
+    InvalidLanguage(String),
 }
 
 pub type Result<T> = std::result::Result<T, JogenError>;
@@ -1,23 +1,65 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 
+use crate::graph::GraphTraversal;
 use crate::object_store::{ObjectStore, ObjectType};
-use crate::objects::directory::{Directory, DirectoryEntry, EntryMode};
 use crate::objects::blob::Blob;
+use crate::objects::directory::{Directory, DirectoryEntry, EntryMode};
+use crate::objects::snapshot::Snapshot;
 use crate::objects::JogenObject;
+use crate::{JogenError, Result};
 
 pub struct MergeResult {
     pub tree_hash: Option<String>,
     pub conflicts: Vec<MergeConflict>,
 }
 
+/// A path that could not be merged automatically.
+/// The merged tree keeps the HEAD side; `incoming` holds the target side (None if it was deleted).
 pub struct MergeConflict {
     pub path: String,
-    pub incoming: MergeConflictIncoming,
+    pub kind: ConflictKind,
+    pub base: Option<DirectoryEntry>,
+    pub head: Option<DirectoryEntry>,
+    pub incoming: Option<DirectoryEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Both sides edited a text file and the edits overlap.
+    Content,
+    /// Both sides edited a binary file. Binaries are never text-merged.
+    Binary,
+    /// Both sides added different content at the same path.
+    AddAdd,
+    /// One side deleted the entry while the other modified it.
+    ModifyDelete,
+    /// One side has a directory where the other has a file.
+    DirectoryFile,
+}
+
+impl ConflictKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Content => "content",
+            Self::Binary => "binary",
+            Self::AddAdd => "add/add",
+            Self::ModifyDelete => "modify/delete",
+            Self::DirectoryFile => "directory/file",
+        }
+    }
+}
+
+impl fmt::Display for ConflictKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
 }
 
-pub enum MergeConflictIncoming {
-    BlobHash(String),
-    Deleted,
+/// Outcome of merging the content of a single blob.
+enum BlobMerge {
+    Merged(String),
+    Conflict(ConflictKind),
 }
 
 pub struct MergeEngine<'a> {
@@ -29,26 +71,103 @@ impl<'a> MergeEngine<'a> {
         Self { store }
     }
 
+    /// Computes the base tree for a three-way merge between two snapshots.
+    ///
+    /// With a single best common ancestor this is simply its tree. Criss-cross histories can
+    /// have several; they are then merged together into a virtual base tree, recursively using
+    /// their own merge base. Conflicts inside the virtual base keep the newer base's side.
+    /// Returns None if the snapshots share no history. The returned tree is the base the merge
+    /// actually uses, so it is what paused operations should record.
+    pub fn merge_base_tree(
+        &self,
+        graph: &GraphTraversal,
+        head: &str,
+        target: &str,
+    ) -> Result<Option<String>> {
+        self.combined_merge_base_tree(graph, &[head], target)
+    }
+
+    /// Computes the base tree for merging `target` into a tree that already combines several
+    /// snapshots, e.g. HEAD and the targets merged before it in an octopus integration.
+    ///
+    /// The merge bases are the best common ancestors of `target` with any of `heads`. Bases
+    /// that are ancestors of another base are dropped; several remaining bases are merged into
+    /// a virtual base tree as in `merge_base_tree`. With a single head this is `merge_base_tree`.
+    pub fn combined_merge_base_tree(
+        &self,
+        graph: &GraphTraversal,
+        heads: &[&str],
+        target: &str,
+    ) -> Result<Option<String>> {
+        let mut candidates: Vec<String> = Vec::new();
+        for head in heads {
+            for base in graph.merge_bases(head, target)? {
+                if !candidates.contains(&base) {
+                    candidates.push(base);
+                }
+            }
+        }
+
+        let mut bases = Vec::new();
+        for base in &candidates {
+            let mut below_another = false;
+            for other in &candidates {
+                if other != base && graph.is_ancestor(base, other)? {
+                    below_another = true;
+                    break;
+                }
+            }
+            if !below_another {
+                bases.push((graph.node(base)?.timestamp, base.clone()));
+            }
+        }
+        // Newest first, as `GraphTraversal::merge_bases` orders them.
+        bases.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        let mut bases = bases.into_iter().map(|(_, hash)| hash);
+
+        let Some(first) = bases.next() else {
+            return Ok(None);
+        };
+
+        let mut virtual_tree = self.snapshot_tree(&first)?;
+
+        for next in bases {
+            let inner_base = self.merge_base_tree(graph, &first, &next)?;
+            let next_tree = self.snapshot_tree(&next)?;
+            let result = self.merge_trees(
+                inner_base.as_deref(),
+                Some(&virtual_tree),
+                Some(&next_tree),
+                "",
+            )?;
+            if let Some(tree_hash) = result.tree_hash {
+                virtual_tree = tree_hash;
+            }
+        }
+
+        Ok(Some(virtual_tree))
+    }
+
     /// Merges three directory trees in memory.
     /// Returns a MergeResult containing the partially merged tree (keeping Head for conflicts)
-    /// and a list of conflicted paths with their incoming target hashes.
+    /// and a list of conflicted paths with both sides of the conflict.
     pub fn merge_trees(
         &self,
         base_hash: Option<&str>,
         head_hash: Option<&str>,
         target_hash: Option<&str>,
         current_path: &str,
-    ) -> MergeResult {
+    ) -> Result<MergeResult> {
         if head_hash == target_hash {
-            return MergeResult {
+            return Ok(MergeResult {
                 tree_hash: head_hash.map(|s| s.to_string()),
                 conflicts: vec![],
-            };
+            });
         }
 
-        let base_dir = self.load_directory_opt(base_hash);
-        let head_dir = self.load_directory_opt(head_hash);
-        let target_dir = self.load_directory_opt(target_hash);
+        let base_dir = self.load_directory_opt(base_hash)?;
+        let head_dir = self.load_directory_opt(head_hash)?;
+        let target_dir = self.load_directory_opt(target_hash)?;
 
         let mut base_map = self.map_entries(&base_dir);
         let mut head_map = self.map_entries(&head_dir);
@@ -98,11 +217,17 @@ impl<'a> MergeEngine<'a> {
             }
 
             // Both changed it differently
-            match (&head_entry, &target_entry) {
-                (Some(h), Some(t)) if h.mode == EntryMode::Directory && t.mode == EntryMode::Directory => {
-                    let b_hash = base_entry.as_ref().map(|e| e.hash.as_str());
-                    let mut sub_result = self.merge_trees(b_hash, Some(&h.hash), Some(&t.hash), &path);
-                    
+            let conflict_kind = match (&head_entry, &target_entry) {
+                (Some(h), Some(t))
+                    if h.mode == EntryMode::Directory && t.mode == EntryMode::Directory =>
+                {
+                    let b_hash = base_entry
+                        .as_ref()
+                        .filter(|e| e.mode == EntryMode::Directory)
+                        .map(|e| e.hash.as_str());
+                    let mut sub_result =
+                        self.merge_trees(b_hash, Some(&h.hash), Some(&t.hash), &path)?;
+
                     if let Some(merged_hash) = sub_result.tree_hash {
                         merged_dir.add_entry(DirectoryEntry {
                             name,
@@ -111,80 +236,126 @@ impl<'a> MergeEngine<'a> {
                         });
                     }
                     conflicts.append(&mut sub_result.conflicts);
+                    continue;
                 }
-                _ => {
-                    // Try auto-merging first if both are files
-                    let auto_merged = if let (Some(b), Some(h), Some(t)) = (&base_entry, &head_entry, &target_entry) {
-                        if h.mode == EntryMode::File && t.mode == EntryMode::File && b.mode == EntryMode::File {
-                            if let (Ok((_, b_content)), Ok((_, h_content)), Ok((_, t_content))) = (
-                                self.store.read_object(&b.hash),
-                                self.store.read_object(&h.hash),
-                                self.store.read_object(&t.hash)
-                            ) {
-                                if let (Ok(b_str), Ok(h_str), Ok(t_str)) = (
-                                    std::str::from_utf8(&b_content),
-                                    std::str::from_utf8(&h_content),
-                                    std::str::from_utf8(&t_content)
-                                ) {
-                                    let merge_opts = diffy::MergeOptions::new();
-                                    if let Ok(merged_str) = merge_opts.merge(b_str, h_str, t_str) {
-                                        // Auto merge succeeded, write to store
-                                        let blob = Blob::new(merged_str.into_bytes());
-                                        if let Ok(serialized) = blob.serialize() {
-                                            if let Ok(merged_hash) = self.store.write_object(
-                                                serialized.as_ref(),
-                                                ObjectType::Blob
-                                            ) {
-                                                Some(merged_hash)
-                                            } else { None }
-                                        } else { None }
-                                    } else { None }
-                                } else { None }
-                            } else { None }
-                        } else { None }
-                    } else { None };
-
-                    if let Some(merged_hash) = auto_merged {
-                        merged_dir.add_entry(DirectoryEntry {
-                            name,
-                            mode: EntryMode::File,
-                            hash: merged_hash,
-                        });
-                    } else {
-                        // Actual conflict (file vs file text conflict, file vs dir, both modified file differently)
-                        // Keep HEAD's version in the merged tree
-                        if let Some(h) = head_entry {
-                            merged_dir.add_entry(h);
+                (Some(h), Some(t))
+                    if h.mode == EntryMode::Directory || t.mode == EntryMode::Directory =>
+                {
+                    ConflictKind::DirectoryFile
+                }
+                (Some(h), Some(t)) => {
+                    // Both sides are files: mode and content are merged independently.
+                    let base_file = base_entry
+                        .as_ref()
+                        .filter(|e| e.mode != EntryMode::Directory);
+                    let mode = match base_file {
+                        Some(b) if h.mode == b.mode => t.mode,
+                        _ => h.mode,
+                    };
+
+                    match self.merge_file_contents(base_file.map(|b| b.hash.as_str()), &h.hash, &t.hash)? {
+                        BlobMerge::Merged(hash) => {
+                            merged_dir.add_entry(DirectoryEntry { name, mode, hash });
+                            continue;
                         }
-                        
-                        // Record incoming side so the Hydrator can create a conflict marker.
-                        if let Some(t) = target_entry {
-                            conflicts.push(MergeConflict {
-                                path,
-                                incoming: MergeConflictIncoming::BlobHash(t.hash),
+                        BlobMerge::Conflict(kind) => {
+                            // Keep HEAD's content, but still honour the merged mode.
+                            merged_dir.add_entry(DirectoryEntry {
+                                name,
+                                mode,
+                                hash: h.hash.clone(),
                             });
-                        } else {
                             conflicts.push(MergeConflict {
                                 path,
-                                incoming: MergeConflictIncoming::Deleted,
+                                kind,
+                                base: base_entry,
+                                head: head_entry,
+                                incoming: target_entry,
                             });
+                            continue;
                         }
                     }
                 }
+                _ => ConflictKind::ModifyDelete,
+            };
+
+            // Actual conflict: keep HEAD's version in the merged tree and
+            // record both sides so the Hydrator can create a conflict marker.
+            if let Some(h) = &head_entry {
+                merged_dir.add_entry(h.clone());
             }
+
+            conflicts.push(MergeConflict {
+                path,
+                kind: conflict_kind,
+                base: base_entry,
+                head: head_entry,
+                incoming: target_entry,
+            });
         }
 
-        let tree_hash = if let Ok(serialized) = merged_dir.serialize() {
-            self.store
-                .write_object(serialized.as_ref(), merged_dir.object_type())
-                .ok()
+        let serialized = merged_dir.serialize()?;
+        let tree_hash = self
+            .store
+            .write_object(serialized.as_ref(), merged_dir.object_type())?;
+
+        Ok(MergeResult {
+            tree_hash: Some(tree_hash),
+            conflicts,
+        })
+    }
+
+    /// Merges the content of two blobs that both diverged from `base_hash`.
+    /// Returns the hash of the resulting blob, or the kind of conflict if they cannot be combined.
+    fn merge_file_contents(
+        &self,
+        base_hash: Option<&str>,
+        head_hash: &str,
+        target_hash: &str,
+    ) -> Result<BlobMerge> {
+        if head_hash == target_hash || Some(target_hash) == base_hash {
+            return Ok(BlobMerge::Merged(head_hash.to_string()));
+        }
+        if Some(head_hash) == base_hash {
+            return Ok(BlobMerge::Merged(target_hash.to_string()));
+        }
+
+        let b_content = match base_hash {
+            Some(hash) => self.load_blob(hash)?,
+            None => Vec::new(),
+        };
+        let h_content = self.load_blob(head_hash)?;
+        let t_content = self.load_blob(target_hash)?;
+
+        if Blob::is_binary(&b_content) || Blob::is_binary(&h_content) || Blob::is_binary(&t_content) {
+            return Ok(BlobMerge::Conflict(ConflictKind::Binary));
+        }
+
+        let conflict_kind = if base_hash.is_some() {
+            ConflictKind::Content
         } else {
-            None
+            ConflictKind::AddAdd
         };
 
-        MergeResult {
-            tree_hash,
-            conflicts,
+        // `is_binary` guarantees the three buffers are valid UTF-8.
+        let (Ok(b_str), Ok(h_str), Ok(t_str)) = (
+            std::str::from_utf8(&b_content),
+            std::str::from_utf8(&h_content),
+            std::str::from_utf8(&t_content),
+        ) else {
+            return Ok(BlobMerge::Conflict(ConflictKind::Binary));
+        };
+
+        let merge_opts = diffy::MergeOptions::new();
+        match merge_opts.merge(b_str, h_str, t_str) {
+            Ok(merged_str) => {
+                let blob = Blob::new(merged_str.into_bytes());
+                let merged_hash = self
+                    .store
+                    .write_object(blob.serialize()?.as_ref(), blob.object_type())?;
+                Ok(BlobMerge::Merged(merged_hash))
+            }
+            Err(_) => Ok(BlobMerge::Conflict(conflict_kind)),
         }
     }
 
@@ -206,13 +377,39 @@ impl<'a> MergeEngine<'a> {
         map
     }
 
-    fn load_directory_opt(&self, hash: Option<&str>) -> Option<Directory> {
-        let hash = hash?;
-        let (kind, content) = self.store.read_object(hash).ok()?;
-        if kind == ObjectType::Directory {
-            Directory::parse(&content).ok()
-        } else {
-            None
+    fn snapshot_tree(&self, hash: &str) -> Result<String> {
+        let (kind, content) = self.store.read_object(hash)?;
+        if kind != ObjectType::Snapshot {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Snapshot, found {}",
+                kind
+            )));
+        }
+        Ok(Snapshot::deserialize(&content)?.directory_hash)
+    }
+
+    fn load_blob(&self, hash: &str) -> Result<Vec<u8>> {
+        let (kind, content) = self.store.read_object(hash)?;
+        if kind != ObjectType::Blob {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Blob, found {}",
+                kind
+            )));
+        }
+        Ok(content)
+    }
+
+    fn load_directory_opt(&self, hash: Option<&str>) -> Result<Option<Directory>> {
+        let Some(hash) = hash else {
+            return Ok(None);
+        };
+        let (kind, content) = self.store.read_object(hash)?;
+        if kind != ObjectType::Directory {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Dir, found {}",
+                kind
+            )));
         }
+        Directory::parse(&content).map(Some)
     }
 }
@@ -165,6 +165,34 @@ impl ObjectStore {
         Ok((header.kind, data_bytes.to_vec()))
     }
 
+    /// Lists the hashes of all stored objects starting with `prefix`.
+    pub fn find_by_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        if prefix.len() < 2 {
+            return Ok(vec![]);
+        }
+
+        let (subdir, rest) = prefix.split_at(2);
+        let dir_path = self.root_path.join(subdir);
+
+        if !dir_path.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut matches = Vec::new();
+        for entry in fs::read_dir(dir_path).map_err(JogenError::Io)? {
+            let entry = entry.map_err(JogenError::Io)?;
+            if let Ok(name) = entry.file_name().into_string() {
+                if name.starts_with(rest) {
+                    matches.push(format!("{}{}", subdir, name));
+                }
+            }
+        }
+
+        matches.sort();
+
+        Ok(matches)
+    }
+
     pub fn exists(&self, hash_hex: &str) -> bool {
         if hash_hex.len() < 2 {
             return false;
@@ -4,6 +4,9 @@ use crate::object_store::ObjectType;
 use crate::objects::JogenObject;
 use crate::Result;
 
+/// How many leading bytes are inspected when classifying a blob as binary.
+const BINARY_SNIFF_LEN: usize = 8000;
+
 pub struct Blob {
     pub data: Vec<u8>,
 }
@@ -12,6 +15,13 @@ impl Blob {
     pub fn new(data: Vec<u8>) -> Self {
         Self { data }
     }
+
+    /// Classifies content as binary if it contains a NUL byte near the start or is not valid UTF-8.
+    /// Binary blobs are never text-merged or line-diffed.
+    pub fn is_binary(data: &[u8]) -> bool {
+        let sniff = &data[..data.len().min(BINARY_SNIFF_LEN)];
+        sniff.contains(&0) || std::str::from_utf8(data).is_err()
+    }
 }
 
 impl JogenObject for Blob {
@@ -43,6 +43,7 @@ impl PartialOrd for DirectoryEntry {
     }
 }
 
+#[derive(Default)]
 pub struct Directory {
     entries: Vec<DirectoryEntry>,
 }
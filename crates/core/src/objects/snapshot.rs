@@ -4,33 +4,98 @@ use std::fmt::{self, Write};
 use crate::object_store::ObjectType;
 use crate::objects::JogenObject;
 use crate::Result;
-use clap::ValueEnum;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+/// The intent of a snapshot. Contexts other than the built-in ones are declared in
+/// `.jogen/config.toml` and kept verbatim as `Custom`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SnapshotContext {
     Feature,
     Fix,
     Refactor,
     Docs,
     Chore,
+    Revert,
     Merge,
     Initial,
+    /// Uncommitted work set aside with `shelve`. Reserved: these snapshots are never part of
+    /// history and cannot be named as revisions.
+    Shelf,
+    Custom(String),
 }
 
 impl SnapshotContext {
-    pub fn as_str(&self) -> &'static str {
+    pub const BUILT_IN: [SnapshotContext; 8] = [
+        Self::Initial,
+        Self::Feature,
+        Self::Fix,
+        Self::Refactor,
+        Self::Docs,
+        Self::Chore,
+        Self::Revert,
+        Self::Merge,
+    ];
+
+    pub fn as_str(&self) -> &str {
         match self {
             Self::Feature => "feature",
             Self::Fix => "fix",
             Self::Refactor => "refactor",
             Self::Docs => "docs",
             Self::Chore => "chore",
+            Self::Revert => "revert",
             Self::Merge => "merge",
             Self::Initial => "initial",
+            Self::Shelf => "shelf",
+            Self::Custom(name) => name,
+        }
+    }
+
+    pub fn is_built_in(&self) -> bool {
+        !matches!(self, Self::Custom(_))
+    }
+
+    /// Intended use of a built-in context. Custom contexts are described in the configuration.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Feature => "new behavior",
+            Self::Fix => "bug fix",
+            Self::Refactor => "behavior-preserving restructure",
+            Self::Docs => "documentation",
+            Self::Chore => "maintenance",
+            Self::Revert => "snapshot undoing an earlier one",
+            Self::Merge => "integration snapshot",
+            Self::Initial => "first project state",
+            Self::Shelf => "shelved workspace changes",
+            Self::Custom(_) => "",
+        }
+    }
+}
+
+impl From<&str> for SnapshotContext {
+    fn from(value: &str) -> Self {
+        match value {
+            "feature" => Self::Feature,
+            "fix" => Self::Fix,
+            "refactor" => Self::Refactor,
+            "docs" => Self::Docs,
+            "chore" => Self::Chore,
+            "revert" => Self::Revert,
+            "merge" => Self::Merge,
+            "initial" => Self::Initial,
+            "shelf" => Self::Shelf,
+            other => Self::Custom(other.to_string()),
         }
     }
 }
 
+impl std::str::FromStr for SnapshotContext {
+    type Err = std::convert::Infallible;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(Self::from(value))
+    }
+}
+
 impl fmt::Display for SnapshotContext {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.as_str())
@@ -44,6 +109,9 @@ pub struct Snapshot {
     pub author: String,
     pub timestamp: i64,
     pub context: SnapshotContext,
+    /// Additional `key value` header lines, e.g. `ported-from <hash>`. Unknown headers are
+    /// preserved verbatim when a snapshot is read.
+    pub headers: Vec<(String, String)>,
     pub message: String,
 }
 
@@ -62,9 +130,33 @@ impl Snapshot {
             author,
             timestamp,
             context,
+            headers: Vec::new(),
             message,
         }
     }
+
+    /// Adds an extra header line. Keys and values must not contain newlines, and keys no spaces.
+    pub fn with_header(mut self, key: &str, value: &str) -> Self {
+        self.headers.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Returns the value of the first extra header named `key`.
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns every value of a header that may appear several times, such as `task`.
+    pub fn header_values(&self, key: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
 }
 
 impl JogenObject for Snapshot {
@@ -78,6 +170,11 @@ impl JogenObject for Snapshot {
             + (8 + self.author.len())
             + 26
             + (9 + self.context.as_str().len())
+            + self
+                .headers
+                .iter()
+                .map(|(k, v)| k.len() + v.len() + 2)
+                .sum::<usize>()
             + self.message.len()
             + 2;
 
@@ -103,6 +200,13 @@ impl JogenObject for Snapshot {
         out.push_str(self.context.as_str());
         out.push('\n');
 
+        for (key, value) in &self.headers {
+            out.push_str(key);
+            out.push(' ');
+            out.push_str(value);
+            out.push('\n');
+        }
+
         out.push('\n');
         out.push_str(&self.message);
 
@@ -120,9 +224,10 @@ impl Snapshot {
         let mut author = String::new();
         let mut timestamp = 0i64;
         let mut context = SnapshotContext::Chore;
+        let mut headers = Vec::new();
         let mut message_lines = Vec::new();
 
-        while let Some(line) = lines.next() {
+        for line in lines.by_ref() {
             if line.is_empty() {
                 break;
             }
@@ -136,19 +241,8 @@ impl Snapshot {
                 "parent" => parent_hashes.push(value.to_string()),
                 "author" => author = value.to_string(),
                 "time" => timestamp = value.parse().unwrap_or(0),
-                "context" => {
-                    context = match value {
-                        "feature" => SnapshotContext::Feature,
-                        "fix" => SnapshotContext::Fix,
-                        "refactor" => SnapshotContext::Refactor,
-                        "docs" => SnapshotContext::Docs,
-                        "chore" => SnapshotContext::Chore,
-                        "merge" => SnapshotContext::Merge,
-                        "initial" => SnapshotContext::Initial,
-                        _ => SnapshotContext::Chore,
-                    }
-                }
-                _ => {}
+                "context" => context = SnapshotContext::from(value),
+                _ => headers.push((key.to_string(), value.to_string())),
             }
         }
 
@@ -164,6 +258,7 @@ impl Snapshot {
             author,
             timestamp,
             context,
+            headers,
             message,
         })
     }
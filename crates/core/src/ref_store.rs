@@ -6,10 +6,118 @@ pub struct RefStore {
 }
 
 pub struct IntegrationStatus {
+    /// The base the paused merge used: a snapshot hash, or for integrations the hash of the base
+    /// tree, which is a virtual tree when the histories have several merge bases.
     pub base_hash: String,
     pub target_hash: String,
     pub target_name: String,
     pub conflict_paths: Vec<String>,
+    pub operation: IntegrationOperation,
+    /// Text conflicts whose resolution is recorded on `--continue`, as (key, path) pairs.
+    pub recorded_conflicts: Vec<(String, String)>,
+    /// Paths resolved automatically from a recorded resolution.
+    pub reused_resolutions: Vec<String>,
+    /// For a paused checkout, where it started.
+    pub checkout_origin: Option<CheckoutOrigin>,
+}
+
+/// The state a `checkout --merge` started from, so that aborting it can return there.
+pub struct CheckoutOrigin {
+    /// The track HEAD was on, or None if it was detached.
+    pub track: Option<String>,
+    pub head_hash: String,
+    /// The workspace with its uncommitted changes, indexed before the checkout.
+    pub workspace_tree: String,
+}
+
+/// The command that paused on conflicts. All of them share `.jogen/INTEGRATING`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrationOperation {
+    /// Merging a target into the current track.
+    Integrate,
+    /// Applying a single snapshot's change onto HEAD.
+    Port,
+    /// Applying the inverse of a single snapshot's change onto HEAD.
+    Revert,
+    /// Merging uncommitted changes onto a checked-out snapshot.
+    Checkout,
+}
+
+impl IntegrationOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Integrate => "integrate",
+            Self::Port => "port",
+            Self::Revert => "revert",
+            Self::Checkout => "checkout",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "integrate" => Some(Self::Integrate),
+            "port" => Some(Self::Port),
+            "revert" => Some(Self::Revert),
+            "checkout" => Some(Self::Checkout),
+            _ => None,
+        }
+    }
+}
+
+/// A verdict given to a snapshot while bisecting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BisectVerdict {
+    Good,
+    Bad,
+    Skip,
+}
+
+impl BisectVerdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Good => "good",
+            Self::Bad => "bad",
+            Self::Skip => "skip",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "good" => Some(Self::Good),
+            "bad" => Some(Self::Bad),
+            "skip" => Some(Self::Skip),
+            _ => None,
+        }
+    }
+}
+
+/// A bisect in progress, stored in `.jogen/BISECT`.
+pub struct BisectState {
+    /// The track HEAD pointed at when bisecting started, if any.
+    pub origin_track: Option<String>,
+    /// The snapshot HEAD pointed at when bisecting started.
+    pub origin_hash: String,
+    /// Every verdict given so far, in order.
+    pub verdicts: Vec<(BisectVerdict, String)>,
+}
+
+impl BisectState {
+    /// The most recent snapshot marked bad.
+    pub fn bad(&self) -> Option<&str> {
+        self.verdicts
+            .iter()
+            .rev()
+            .find(|(verdict, _)| *verdict == BisectVerdict::Bad)
+            .map(|(_, hash)| hash.as_str())
+    }
+
+    pub fn marked(&self, verdict: BisectVerdict) -> Vec<String> {
+        self.verdicts
+            .iter()
+            .filter(|(v, _)| *v == verdict)
+            .map(|(_, hash)| hash.clone())
+            .collect()
+    }
 }
 
 impl RefStore {
@@ -77,6 +185,11 @@ impl RefStore {
         }
     }
 
+    /// Points HEAD directly at a snapshot, leaving every track untouched.
+    pub fn detach_head(&self, hash: &str) -> Result<()> {
+        self.update_ref("HEAD", hash)
+    }
+
     pub fn set_head_to_track(&self, track_name: &str) -> Result<()> {
         let head_path = self.root_path.join(".jogen/HEAD");
         let content = format!("ref: refs/tracks/{}\n", track_name);
@@ -104,27 +217,94 @@ impl RefStore {
     }
 
     pub fn list_tracks(&self) -> Result<Vec<String>> {
-        let path = self.root_path.join(".jogen/refs/tracks");
+        self.list_refs("refs/tracks")
+    }
+
+    pub fn resolve_label(&self, label_name: &str) -> Result<Option<String>> {
+        self.read_ref(&format!("refs/labels/{}", label_name))
+    }
+
+    /// Labels are fixed names for a snapshot. Unlike tracks, they never move.
+    pub fn create_label(&self, label_name: &str, hash: &str) -> Result<()> {
+        let path = self.root_path.join(".jogen/refs/labels").join(label_name);
+
+        if path.exists() {
+            return Err(JogenError::Io(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("Label '{}' already exists", label_name),
+            )));
+        }
+
+        self.update_ref(&format!("refs/labels/{}", label_name), hash)
+    }
+
+    pub fn list_labels(&self) -> Result<Vec<String>> {
+        self.list_refs("refs/labels")
+    }
+
+    /// Shelves hold work set aside with `jogen shelve`. They are numbered in the order they were
+    /// pushed and live outside `refs/tracks`, so they never appear in track history.
+    pub fn push_shelf(&self, hash: &str) -> Result<usize> {
+        let id = self.list_shelves()?.first().map(|(id, _)| id + 1).unwrap_or(0);
+        self.update_ref(&format!("refs/shelves/{}", id), hash)?;
+        Ok(id)
+    }
+
+    /// Lists shelves as `(id, snapshot hash)`, newest first.
+    pub fn list_shelves(&self) -> Result<Vec<(usize, String)>> {
+        let mut shelves = Vec::new();
+        for name in self.list_refs("refs/shelves")? {
+            let Ok(id) = name.parse::<usize>() else {
+                continue;
+            };
+            if let Some(hash) = self.read_ref(&format!("refs/shelves/{}", name))? {
+                shelves.push((id, hash));
+            }
+        }
+        shelves.sort_by_key(|(id, _)| std::cmp::Reverse(*id));
+        Ok(shelves)
+    }
+
+    pub fn resolve_shelf(&self, id: usize) -> Result<Option<String>> {
+        self.read_ref(&format!("refs/shelves/{}", id))
+    }
+
+    pub fn drop_shelf(&self, id: usize) -> Result<()> {
+        let path = self.root_path.join(".jogen/refs/shelves").join(id.to_string());
+
+        if !path.exists() {
+            return Err(JogenError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Shelf {} does not exist", id),
+            )));
+        }
+
+        fs::remove_file(path).map_err(JogenError::Io)?;
+        Ok(())
+    }
+
+    fn list_refs(&self, namespace: &str) -> Result<Vec<String>> {
+        let path = self.root_path.join(".jogen").join(namespace);
 
         if !path.exists() {
             return Ok(vec![]);
         }
 
-        let mut tracks = Vec::new();
+        let mut names = Vec::new();
 
         for entry in fs::read_dir(path).map_err(JogenError::Io)? {
             let entry = entry.map_err(JogenError::Io)?;
 
             if let Ok(name) = entry.file_name().into_string() {
                 if !name.starts_with('.') {
-                    tracks.push(name);
+                    names.push(name);
                 }
             }
         }
 
-        tracks.sort();
+        names.sort();
 
-        Ok(tracks)
+        Ok(names)
     }
 
     pub fn current_track(&self) -> Result<Option<String>> {
@@ -166,6 +346,7 @@ impl RefStore {
         target_hash: &str,
         target_name: &str,
         conflict_paths: &[String],
+        operation: IntegrationOperation,
     ) -> Result<()> {
         let path = self.root_path.join(".jogen/INTEGRATING");
         let mut content = format!(
@@ -179,6 +360,8 @@ impl RefStore {
             content.push('\n');
             content.push_str(conflict_path);
         }
+        content.push_str("\noperation ");
+        content.push_str(operation.as_str());
         fs::write(path, content).map_err(JogenError::Io)?;
         Ok(())
     }
@@ -211,14 +394,135 @@ impl RefStore {
             }
         }
 
+        // Files written before the operation line existed are integrations.
+        let mut operation = IntegrationOperation::Integrate;
+        let mut recorded_conflicts = Vec::new();
+        let mut reused_resolutions = Vec::new();
+        let mut origin_track = None;
+        let mut origin_head = None;
+        let mut origin_workspace = None;
+        for line in lines {
+            if let Some(value) = line.strip_prefix("operation ") {
+                operation = IntegrationOperation::parse(value).unwrap_or(operation);
+            } else if let Some(value) = line.strip_prefix("resolution ") {
+                if let Some((key, path)) = value.split_once(' ') {
+                    recorded_conflicts.push((key.to_string(), path.to_string()));
+                }
+            } else if let Some(path) = line.strip_prefix("reused ") {
+                reused_resolutions.push(path.to_string());
+            } else if let Some(track) = line.strip_prefix("origin-track ") {
+                origin_track = Some(track.to_string());
+            } else if let Some(hash) = line.strip_prefix("origin-head ") {
+                origin_head = Some(hash.to_string());
+            } else if let Some(tree) = line.strip_prefix("origin-workspace ") {
+                origin_workspace = Some(tree.to_string());
+            }
+        }
+
+        let checkout_origin = match (origin_head, origin_workspace) {
+            (Some(head_hash), Some(workspace_tree)) => Some(CheckoutOrigin {
+                track: origin_track,
+                head_hash,
+                workspace_tree,
+            }),
+            _ => None,
+        };
+
         Ok(Some(IntegrationStatus {
             base_hash: base,
             target_hash,
             target_name,
             conflict_paths,
+            operation,
+            recorded_conflicts,
+            reused_resolutions,
+            checkout_origin,
         }))
     }
 
+    /// Adds conflict resolution bookkeeping to the paused operation started by `begin_integration`.
+    pub fn record_resolutions(
+        &self,
+        recorded_conflicts: &[(String, String)],
+        reused_resolutions: &[String],
+    ) -> Result<()> {
+        let path = self.root_path.join(".jogen/INTEGRATING");
+        let mut content = fs::read_to_string(&path).map_err(JogenError::Io)?;
+        for (key, conflict_path) in recorded_conflicts {
+            content.push_str(&format!("\nresolution {} {}", key, conflict_path));
+        }
+        for reused_path in reused_resolutions {
+            content.push_str(&format!("\nreused {}", reused_path));
+        }
+        fs::write(path, content).map_err(JogenError::Io)?;
+        Ok(())
+    }
+
+    /// Adds where a checkout started to the paused operation started by `begin_integration`.
+    pub fn record_checkout_origin(&self, origin: &CheckoutOrigin) -> Result<()> {
+        let path = self.root_path.join(".jogen/INTEGRATING");
+        let mut content = fs::read_to_string(&path).map_err(JogenError::Io)?;
+        if let Some(track) = &origin.track {
+            content.push_str(&format!("\norigin-track {}", track));
+        }
+        content.push_str(&format!("\norigin-head {}", origin.head_hash));
+        content.push_str(&format!("\norigin-workspace {}", origin.workspace_tree));
+        fs::write(path, content).map_err(JogenError::Io)?;
+        Ok(())
+    }
+
+    pub fn write_bisect_state(&self, state: &BisectState) -> Result<()> {
+        let path = self.root_path.join(".jogen/BISECT");
+        let mut content = String::new();
+        if let Some(track) = &state.origin_track {
+            content.push_str(&format!("track {}\n", track));
+        }
+        content.push_str(&format!("head {}\n", state.origin_hash));
+        for (verdict, hash) in &state.verdicts {
+            content.push_str(&format!("{} {}\n", verdict.as_str(), hash));
+        }
+        fs::write(path, content).map_err(JogenError::Io)?;
+        Ok(())
+    }
+
+    pub fn get_bisect_state(&self) -> Result<Option<BisectState>> {
+        let path = self.root_path.join(".jogen/BISECT");
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path).map_err(JogenError::Io)?;
+        let mut state = BisectState {
+            origin_track: None,
+            origin_hash: String::new(),
+            verdicts: Vec::new(),
+        };
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once(' ') else {
+                continue;
+            };
+            match key {
+                "track" => state.origin_track = Some(value.to_string()),
+                "head" => state.origin_hash = value.to_string(),
+                _ => {
+                    if let Some(verdict) = BisectVerdict::parse(key) {
+                        state.verdicts.push((verdict, value.to_string()));
+                    }
+                }
+            }
+        }
+
+        Ok(Some(state))
+    }
+
+    pub fn clear_bisect(&self) -> Result<()> {
+        let path = self.root_path.join(".jogen/BISECT");
+        if path.exists() {
+            fs::remove_file(path).map_err(JogenError::Io)?;
+        }
+        Ok(())
+    }
+
     pub fn clear_integration(&self) -> Result<()> {
         let path = self.root_path.join(".jogen/INTEGRATING");
         if path.exists() {
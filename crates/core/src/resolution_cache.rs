@@ -0,0 +1,185 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::merge::{ConflictKind, MergeConflict};
+use crate::object_store::{ObjectStore, ObjectType};
+use crate::objects::blob::Blob;
+use crate::{JogenError, Result};
+
+const OURS_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+/// A text conflict in a form that can be matched against recorded resolutions.
+pub struct ConflictPreimage {
+    /// Hash of the conflicting hunks (base, ours and theirs), independent of the
+    /// surrounding lines and of which side is HEAD.
+    pub key: String,
+    /// The whole file with normalized conflict markers around each conflicting hunk.
+    pub text: String,
+}
+
+/// Conflict resolutions recorded by hand and reused by later merges, stored in `.jogen/resolutions`.
+///
+/// Each entry is a directory named after the conflict key. It holds a `preimage` (the normalized
+/// conflicted file) and a `postimage` (the resolved file) once the conflict was resolved, and a
+/// `pending` preimage while an operation paused on it.
+/// A recorded resolution is applied by three-way merging the recorded preimage, the new
+/// preimage and the postimage, so unrelated edits around the conflict are kept.
+pub struct ResolutionCache<'a> {
+    store: &'a ObjectStore,
+    path: PathBuf,
+}
+
+impl<'a> ResolutionCache<'a> {
+    pub fn new(store: &'a ObjectStore, root_path: &Path) -> Self {
+        Self {
+            store,
+            path: root_path.join(".jogen/resolutions"),
+        }
+    }
+
+    /// Builds the preimage of a content or add/add conflict between text files.
+    /// Returns None for other kinds of conflicts, which cannot be resolved automatically.
+    pub fn preimage(&self, conflict: &MergeConflict) -> Result<Option<ConflictPreimage>> {
+        if !matches!(conflict.kind, ConflictKind::Content | ConflictKind::AddAdd) {
+            return Ok(None);
+        }
+        let (Some(head), Some(incoming)) = (&conflict.head, &conflict.incoming) else {
+            return Ok(None);
+        };
+
+        let base = match &conflict.base {
+            Some(entry) => self.load_blob(&entry.hash)?,
+            None => Vec::new(),
+        };
+        let head = self.load_blob(&head.hash)?;
+        let incoming = self.load_blob(&incoming.hash)?;
+
+        if Blob::is_binary(&base) || Blob::is_binary(&head) || Blob::is_binary(&incoming) {
+            return Ok(None);
+        }
+        let (Ok(base), Ok(head), Ok(incoming)) = (
+            String::from_utf8(base),
+            String::from_utf8(head),
+            String::from_utf8(incoming),
+        ) else {
+            return Ok(None);
+        };
+
+        match diffy::MergeOptions::new().merge(&base, &head, &incoming) {
+            Ok(_) => Ok(None),
+            Err(conflicted) => Ok(Some(Self::normalize(&conflicted))),
+        }
+    }
+
+    /// Saves a preimage so that its resolution can be recorded once the conflict is resolved.
+    /// It only replaces the recorded preimage when `record` is called, so the recorded pair
+    /// always describes the same occurrence of the conflict.
+    pub fn remember(&self, preimage: &ConflictPreimage) -> Result<()> {
+        let entry = self.path.join(&preimage.key);
+        fs::create_dir_all(&entry).map_err(JogenError::Io)?;
+        fs::write(entry.join("pending"), &preimage.text).map_err(JogenError::Io)?;
+        Ok(())
+    }
+
+    /// Records how the conflict with the given key was resolved.
+    /// Does nothing if its preimage was never remembered.
+    pub fn record(&self, key: &str, resolved: &str) -> Result<()> {
+        let entry = self.path.join(key);
+        let pending = entry.join("pending");
+        if !pending.exists() {
+            return Ok(());
+        }
+        fs::rename(pending, entry.join("preimage")).map_err(JogenError::Io)?;
+        fs::write(entry.join("postimage"), resolved).map_err(JogenError::Io)?;
+        Ok(())
+    }
+
+    /// Returns the resolved file content if a resolution was recorded for this conflict
+    /// and it still applies cleanly.
+    pub fn resolve(&self, preimage: &ConflictPreimage) -> Result<Option<String>> {
+        let entry = self.path.join(&preimage.key);
+        let postimage_path = entry.join("postimage");
+        if !postimage_path.exists() {
+            return Ok(None);
+        }
+
+        let recorded_preimage = fs::read_to_string(entry.join("preimage")).map_err(JogenError::Io)?;
+        let postimage = fs::read_to_string(postimage_path).map_err(JogenError::Io)?;
+
+        Ok(diffy::MergeOptions::new()
+            .merge(&recorded_preimage, &preimage.text, &postimage)
+            .ok())
+    }
+
+    /// Rewrites diffy's diff3 output so that equivalent conflicts produce the same text:
+    /// marker labels are dropped and the two sides of each hunk are put in a fixed order.
+    /// The key hashes only the conflicting hunks.
+    fn normalize(conflicted: &str) -> ConflictPreimage {
+        let mut text = String::new();
+        let mut hasher = blake3::Hasher::new();
+        let mut lines = conflicted.split_inclusive('\n');
+
+        while let Some(line) = lines.next() {
+            if !line.starts_with(OURS_MARKER) {
+                text.push_str(line);
+                continue;
+            }
+
+            let mut ours = String::new();
+            let mut base = String::new();
+            let mut theirs = String::new();
+            let mut section = &mut ours;
+            for line in lines.by_ref() {
+                if line.starts_with(BASE_MARKER) {
+                    section = &mut base;
+                } else if line.starts_with(SEPARATOR_MARKER) {
+                    section = &mut theirs;
+                } else if line.starts_with(THEIRS_MARKER) {
+                    break;
+                } else {
+                    section.push_str(line);
+                }
+            }
+
+            if theirs < ours {
+                std::mem::swap(&mut ours, &mut theirs);
+            }
+
+            for part in [&ours, &base, &theirs] {
+                hasher.update(part.as_bytes());
+                hasher.update(&[0]);
+            }
+
+            text.push_str(OURS_MARKER);
+            text.push('\n');
+            text.push_str(&ours);
+            text.push_str(BASE_MARKER);
+            text.push('\n');
+            text.push_str(&base);
+            text.push_str(SEPARATOR_MARKER);
+            text.push('\n');
+            text.push_str(&theirs);
+            text.push_str(THEIRS_MARKER);
+            text.push('\n');
+        }
+
+        ConflictPreimage {
+            key: hasher.finalize().to_hex().to_string(),
+            text,
+        }
+    }
+
+    fn load_blob(&self, hash: &str) -> Result<Vec<u8>> {
+        let (kind, content) = self.store.read_object(hash)?;
+        if kind != ObjectType::Blob {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Blob, found {}",
+                kind
+            )));
+        }
+        Ok(content)
+    }
+}
@@ -0,0 +1,132 @@
+use crate::object_store::{ObjectStore, ObjectType};
+use crate::objects::snapshot::{Snapshot, SnapshotContext};
+use crate::ref_store::RefStore;
+use crate::{JogenError, Result};
+
+/// Shortest hash prefix accepted when resolving abbreviated snapshot hashes.
+const MIN_PREFIX_LEN: usize = 4;
+
+/// Resolves user-facing revision strings to snapshot hashes.
+///
+/// Supported forms:
+/// - `HEAD`
+/// - a track name (`main`) or label name (`v1.0`); tracks win over labels
+/// - a full snapshot hash, or a unique prefix of at least four characters; shelves are not
+///   revisions and never match
+/// - any of the above followed by `~N` (N-th first-parent ancestor) or `^N` (N-th parent)
+pub struct RevisionResolver<'a> {
+    store: &'a ObjectStore,
+    refs: &'a RefStore,
+}
+
+impl<'a> RevisionResolver<'a> {
+    pub fn new(store: &'a ObjectStore, refs: &'a RefStore) -> Self {
+        Self { store, refs }
+    }
+
+    pub fn resolve(&self, revision: &str) -> Result<String> {
+        let suffix_start = revision.find(['~', '^']).unwrap_or(revision.len());
+        let (name, mut suffix) = revision.split_at(suffix_start);
+
+        let mut hash = self.resolve_name(name)?;
+
+        while !suffix.is_empty() {
+            let op = suffix.as_bytes()[0];
+            let rest = &suffix[1..];
+            let digits_end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let count = if digits_end == 0 {
+                1
+            } else {
+                rest[..digits_end]
+                    .parse::<usize>()
+                    .map_err(|_| JogenError::RevisionNotFound(revision.to_string()))?
+            };
+            suffix = &rest[digits_end..];
+
+            hash = match op {
+                b'~' => {
+                    let mut current = hash;
+                    for _ in 0..count {
+                        current = self.parent(&current, 0, revision)?;
+                    }
+                    current
+                }
+                _ if count == 0 => hash,
+                _ => self.parent(&hash, count - 1, revision)?,
+            };
+        }
+
+        Ok(hash)
+    }
+
+    /// Resolves a `<from>..<to>` range. An empty side defaults to `HEAD`.
+    pub fn resolve_range(&self, range: &str) -> Result<(String, String)> {
+        let (from, to) = range
+            .split_once("..")
+            .ok_or_else(|| JogenError::RevisionNotFound(range.to_string()))?;
+        let from = if from.is_empty() { "HEAD" } else { from };
+        let to = if to.is_empty() { "HEAD" } else { to };
+        Ok((self.resolve(from)?, self.resolve(to)?))
+    }
+
+    fn resolve_name(&self, name: &str) -> Result<String> {
+        if name.is_empty() {
+            return Err(JogenError::RevisionNotFound(name.to_string()));
+        }
+
+        if name == "HEAD" {
+            return self
+                .refs
+                .read_head()?
+                .ok_or_else(|| JogenError::RevisionNotFound(name.to_string()));
+        }
+
+        if let Some(hash) = self.refs.resolve_track(name)? {
+            return Ok(hash);
+        }
+
+        if let Some(hash) = self.refs.resolve_label(name)? {
+            return Ok(hash);
+        }
+
+        if !name.chars().all(|c| c.is_ascii_hexdigit()) || name.len() < MIN_PREFIX_LEN {
+            return Err(JogenError::RevisionNotFound(name.to_string()));
+        }
+
+        let name = name.to_ascii_lowercase();
+        let candidates: Vec<String> = self
+            .store
+            .find_by_prefix(&name)?
+            .into_iter()
+            .filter(|hash| match self.store.read_object(hash) {
+                // Shelves are snapshot objects too, but they are not part of history.
+                Ok((ObjectType::Snapshot, content)) => Snapshot::deserialize(&content)
+                    .is_ok_and(|snapshot| snapshot.context != SnapshotContext::Shelf),
+                _ => false,
+            })
+            .collect();
+
+        match candidates.len() {
+            0 => Err(JogenError::RevisionNotFound(name)),
+            1 => Ok(candidates.into_iter().next().unwrap()),
+            _ => Err(JogenError::AmbiguousRevision(name)),
+        }
+    }
+
+    fn parent(&self, hash: &str, index: usize, revision: &str) -> Result<String> {
+        let (kind, content) = self.store.read_object(hash)?;
+        if kind != ObjectType::Snapshot {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Snapshot, found {}",
+                kind
+            )));
+        }
+        Snapshot::deserialize(&content)?
+            .parent_hashes
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| JogenError::RevisionNotFound(revision.to_string()))
+    }
+}
@@ -1,31 +1,15 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::path::Path;
-use tree_sitter::{Language, Node, Parser, Tree};
+use std::sync::OnceLock;
 
-pub enum SupportedLanguage {
-    Rust,
-    JavaScript,
-    Python,
-}
-
-impl SupportedLanguage {
-    pub fn from_extension(ext: &str) -> Option<Self> {
-        match ext {
-            "rs" => Some(Self::Rust),
-            "js" | "ts" | "jsx" | "tsx" => Some(Self::JavaScript),
-            "py" => Some(Self::Python),
-            _ => None,
-        }
-    }
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Node, Parser, Query, QueryCursor, Tree};
 
-    pub fn get_tree_sitter_language(&self) -> Language {
-        match self {
-            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
-            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
-            Self::Python => tree_sitter_python::LANGUAGE.into(),
-        }
-    }
-}
+use crate::language::{BuiltinLanguage, LanguageDefinition};
+use crate::{JogenError, Result};
 
+#[derive(Debug, Clone)]
 pub struct SemanticBlock {
     pub kind: String,
     pub name: String,
@@ -33,58 +17,247 @@ pub struct SemanticBlock {
     pub start_line: usize,
     pub end_line: usize,
     pub breadcrumbs: Vec<String>,
+    /// Whether the language's block query marks the block as part of the public API.
+    pub public: bool,
+}
+
+/// One step of a symbol path: a block name, optionally preceded by a keyword such as `impl`,
+/// `fn` or `class` that must match the block's kind.
+struct SymbolSegment {
+    keyword: Option<String>,
+    name: String,
 }
 
-pub struct SemanticEngine;
+/// A `>`-separated path to a named block, following its breadcrumbs, e.g.
+/// `impl Hydrator > apply_diff` or `class Parser > fn parse`.
+/// Leading segments may be omitted: `apply_diff` matches the block wherever it is nested.
+pub struct SymbolPath {
+    segments: Vec<SymbolSegment>,
+}
 
-impl Default for SemanticEngine {
-    fn default() -> Self {
-        Self::new()
+impl SymbolPath {
+    /// Parses a symbol path. Returns None if it is empty or has an empty segment.
+    pub fn parse(path: &str) -> Option<Self> {
+        let mut segments = Vec::new();
+        for segment in path.split('>') {
+            let mut words: Vec<&str> = segment.split_whitespace().collect();
+            let name = words.pop()?.to_string();
+            let keyword = match words.as_slice() {
+                [] => None,
+                [keyword] => Some(keyword.to_string()),
+                _ => return None,
+            };
+            segments.push(SymbolSegment { keyword, name });
+        }
+        Some(Self { segments })
     }
+
+    /// Returns every block matching the path. `blocks` must be in the order returned by
+    /// `SemanticEngine::extract_blocks`.
+    pub fn find<'b>(&self, blocks: &'b [SemanticBlock]) -> Vec<&'b SemanticBlock> {
+        let Some((last, enclosing)) = self.segments.split_last() else {
+            return Vec::new();
+        };
+
+        blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| last.matches(block))
+            .filter(|(index, block)| {
+                // Blocks are listed in pre-order, so the ancestor at each depth is the closest
+                // preceding block with that many breadcrumbs.
+                let ancestors: Vec<&SemanticBlock> = (0..block.breadcrumbs.len())
+                    .filter_map(|depth| {
+                        blocks[..*index]
+                            .iter()
+                            .rev()
+                            .find(|candidate| candidate.breadcrumbs.len() == depth)
+                    })
+                    .collect();
+
+                enclosing.len() <= ancestors.len()
+                    && enclosing
+                        .iter()
+                        .rev()
+                        .zip(ancestors.iter().rev())
+                        .all(|(segment, ancestor)| segment.matches(ancestor))
+            })
+            .map(|(_, block)| block)
+            .collect()
+    }
+}
+
+impl SymbolSegment {
+    fn matches(&self, block: &SemanticBlock) -> bool {
+        if block.name != self.name {
+            return false;
+        }
+        let Some(keyword) = &self.keyword else {
+            return true;
+        };
+        let keyword = match keyword.as_str() {
+            "fn" | "def" | "func" => "function",
+            other => other,
+        };
+        block.kind.split('_').any(|word| word == keyword)
+            || (keyword == "function" && block.kind.contains("method"))
+    }
+}
+
+/// A language known to the engine, with its block query compiled.
+pub struct RegisteredLanguage {
+    definition: Box<dyn LanguageDefinition>,
+    query: Query,
+    block_capture: u32,
+    name_capture: Option<u32>,
+    public_capture: Option<u32>,
+}
+
+impl RegisteredLanguage {
+    fn compile(definition: Box<dyn LanguageDefinition>) -> Result<Self> {
+        let query = Query::new(&definition.grammar(), definition.block_query())
+            .map_err(|e| JogenError::InvalidLanguage(format!("{}: {}", definition.name(), e)))?;
+        let block_capture = query.capture_index_for_name("block").ok_or_else(|| {
+            JogenError::InvalidLanguage(format!("{}: the block query has no @block capture", definition.name()))
+        })?;
+        let name_capture = query.capture_index_for_name("name");
+        let public_capture = query.capture_index_for_name("public");
+
+        Ok(Self {
+            definition,
+            query,
+            block_capture,
+            name_capture,
+            public_capture,
+        })
+    }
+
+    pub fn definition(&self) -> &dyn LanguageDefinition {
+        self.definition.as_ref()
+    }
+}
+
+/// The built-in languages, compiled on first use and shared by every engine.
+static BUILTIN_LANGUAGES: OnceLock<std::result::Result<Vec<RegisteredLanguage>, String>> = OnceLock::new();
+
+/// Parses files and extracts their semantic blocks, using the language registered for
+/// each file extension.
+pub struct SemanticEngine {
+    builtin: &'static [RegisteredLanguage],
+    registered: Vec<RegisteredLanguage>,
 }
 
 impl SemanticEngine {
-    pub fn new() -> Self {
-        Self
+    /// Creates an engine that knows the built-in languages. Their block queries are compiled
+    /// once per process.
+    pub fn new() -> Result<Self> {
+        let builtin = BUILTIN_LANGUAGES.get_or_init(|| {
+            BuiltinLanguage::ALL
+                .into_iter()
+                .map(|language| RegisteredLanguage::compile(Box::new(language)).map_err(|e| e.to_string()))
+                .collect()
+        });
+        let builtin = builtin.as_ref().map_err(|e| JogenError::InvalidLanguage(e.clone()))?;
+
+        Ok(Self {
+            builtin,
+            registered: Vec::new(),
+        })
+    }
+
+    /// Adds a language. It takes precedence over languages registered before it for the
+    /// same extensions, so a built-in language can be replaced.
+    pub fn register(&mut self, definition: impl LanguageDefinition + 'static) -> Result<()> {
+        self.registered.push(RegisteredLanguage::compile(Box::new(definition))?);
+        Ok(())
+    }
+
+    /// Every language, in the order they were registered.
+    fn languages(&self) -> impl DoubleEndedIterator<Item = &RegisteredLanguage> {
+        self.builtin.iter().chain(&self.registered)
+    }
+
+    /// A hash of every registered language's extensions and block query. Blocks extracted by
+    /// engines with the same fingerprint are the same, so cached results can be keyed by it.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = blake3::Hasher::new();
+        for language in self.languages() {
+            let definition = language.definition();
+            for part in [definition.name(), definition.block_query()]
+                .into_iter()
+                .chain(definition.extensions().iter().copied())
+                .chain(definition.identifier_kinds().iter().copied())
+            {
+                hasher.update(part.as_bytes());
+                hasher.update(&[0]);
+            }
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// The language handling files with this extension, if any.
+    pub fn language_for(&self, extension: &str) -> Option<&RegisteredLanguage> {
+        self.languages()
+            .rev()
+            .find(|language| language.definition.extensions().contains(&extension))
     }
 
     /// Attempts to parse the file content into an AST if the language is supported.
-    pub fn parse_file(&self, path: &Path, content: &[u8]) -> Option<(SupportedLanguage, Tree)> {
+    pub fn parse_file(&self, path: &Path, content: &[u8]) -> Option<(&RegisteredLanguage, Tree)> {
         let ext = path.extension()?.to_str()?;
-        let lang = SupportedLanguage::from_extension(ext)?;
+        let language = self.language_for(ext)?;
 
         let mut parser = Parser::new();
-        if parser.set_language(&lang.get_tree_sitter_language()).is_err() {
+        if parser.set_language(&language.definition.grammar()).is_err() {
             return None;
         }
 
         let tree = parser.parse(content, None)?;
-        Some((lang, tree))
+        Some((language, tree))
     }
 
-    /// Extracts semantic blocks recursively from an AST.
-    pub fn extract_blocks(&self, tree: &Tree, content: &[u8]) -> Vec<SemanticBlock> {
+    /// Extracts the blocks selected by the language's block query, in the order they appear
+    /// in the file. Enclosing blocks come before the blocks nested in them.
+    pub fn extract_blocks(&self, language: &RegisteredLanguage, tree: &Tree, content: &[u8]) -> Vec<SemanticBlock> {
+        // Each block with its name node and whether a pattern marked it public.
+        let mut found: Vec<(Node, Option<Node>, bool)> = Vec::new();
+        let mut positions: HashMap<usize, usize> = HashMap::new();
+
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&language.query, tree.root_node(), content);
+        while let Some(m) = matches.next() {
+            let capture = |index: u32| m.captures.iter().find(|c| c.index == index).map(|c| c.node);
+            let Some(block) = capture(language.block_capture) else {
+                continue;
+            };
+            let public = language.public_capture.and_then(capture).is_some();
+
+            // A node matched by several patterns keeps the first name, and is public if any
+            // pattern says so.
+            match positions.get(&block.id()) {
+                Some(&position) => found[position].2 |= public,
+                None => {
+                    positions.insert(block.id(), found.len());
+                    found.push((block, language.name_capture.and_then(capture), public));
+                }
+            }
+        }
+        found.sort_by_key(|(node, _, _)| (node.start_byte(), Reverse(node.end_byte())));
+
+        let identifiers = language.definition.identifier_kinds();
         let mut blocks = Vec::new();
-        self.collect_blocks(tree.root_node(), content, Vec::new(), &mut blocks);
-        blocks
-    }
+        // End byte and name of each block enclosing the current one.
+        let mut enclosing: Vec<(usize, String)> = Vec::new();
 
-    fn collect_blocks(&self, node: Node, content: &[u8], breadcrumbs: Vec<String>, blocks: &mut Vec<SemanticBlock>) {
-        let kind = node.kind();
-        let is_block = kind.contains("function") 
-            || kind.contains("class") 
-            || kind.contains("struct") 
-            || kind.contains("impl") 
-            || kind.contains("declaration")
-            || kind.contains("import")
-            || kind.contains("export")
-            || kind.contains("static")
-            || kind.contains("const");
-
-        let mut new_breadcrumbs = breadcrumbs.clone();
-
-        if is_block {
-            let name = self.find_identifier(node, content).unwrap_or_else(|| "<unnamed>".to_string());
+        for (node, name_node, public) in found {
+            while enclosing.last().is_some_and(|(end, _)| *end <= node.start_byte()) {
+                enclosing.pop();
+            }
+
+            let name = name_node
+                .and_then(|child| self.find_identifier(child, content, identifiers))
+                .or_else(|| self.find_identifier(node, content, identifiers))
+                .unwrap_or_else(|| "<unnamed>".to_string());
             let block_content = if let Ok(s) = std::str::from_utf8(&content[node.start_byte()..node.end_byte()]) {
                 s.to_string()
             } else {
@@ -92,43 +265,40 @@ impl SemanticEngine {
             };
 
             blocks.push(SemanticBlock {
-                kind: kind.to_string(),
+                kind: node.kind().to_string(),
                 name: name.clone(),
                 content: block_content,
                 start_line: node.start_position().row + 1,
                 end_line: node.end_position().row + 1,
-                breadcrumbs: breadcrumbs.clone(),
+                breadcrumbs: enclosing.iter().map(|(_, name)| name.clone()).collect(),
+                public,
             });
-            new_breadcrumbs.push(name);
+            enclosing.push((node.end_byte(), name));
         }
 
-        let mut cursor = node.walk();
-        for child in node.children(&mut cursor) {
-            self.collect_blocks(child, content, new_breadcrumbs.clone(), blocks);
-        }
+        blocks
     }
 
-    /// Recursively searches for the first "identifier" or "name" node to label the block.
-    fn find_identifier(&self, node: Node, content: &[u8]) -> Option<String> {
+    /// Recursively searches for the first identifier node to label the block.
+    fn find_identifier(&self, node: Node, content: &[u8], identifiers: &[&str]) -> Option<String> {
         let kind = node.kind();
-        if kind == "identifier" || kind == "name" || kind == "type_identifier" {
+        if identifiers.contains(&kind) {
             return std::str::from_utf8(&content[node.start_byte()..node.end_byte()])
                 .ok()
                 .map(|s| s.to_string());
         }
 
+        // Prefer fields specifically labeled as "name" in tree-sitter grammars
+        if let Some(id) = node
+            .child_by_field_name("name")
+            .and_then(|child| self.find_identifier(child, content, identifiers))
+        {
+            return Some(id);
+        }
+
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
-            // Prefer fields specifically labeled as "name" in tree-sitter grammars
-            if let Some(field) = child.walk().field_name() {
-                 if field == "name" {
-                     if let Ok(s) = std::str::from_utf8(&content[child.start_byte()..child.end_byte()]) {
-                         return Some(s.to_string());
-                     }
-                 }
-            }
-
-            if let Some(id) = self.find_identifier(child, content) {
+            if let Some(id) = self.find_identifier(child, content, identifiers) {
                 return Some(id);
             }
         }
@@ -0,0 +1,265 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+use crate::object_store::{ObjectStore, ObjectType};
+use crate::objects::blob::Blob;
+use crate::objects::directory::DirectoryEntry;
+use crate::semantic::{SemanticBlock, SemanticEngine};
+use crate::tree::{ChangeKind, TreeReader};
+use crate::{JogenError, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolChangeKind {
+    Added,
+    Removed,
+    /// The block's code changed. It may also have moved.
+    Modified,
+    /// The block's code is unchanged, but it moved to another enclosing block or was
+    /// reordered among its siblings.
+    Moved,
+}
+
+impl SymbolChangeKind {
+    /// Single-character code used when listing changes.
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::Added => '+',
+            Self::Removed => '-',
+            Self::Modified => '~',
+            Self::Moved => '>',
+        }
+    }
+}
+
+/// A block that differs between two versions of a file.
+#[derive(Debug, Clone)]
+pub struct SymbolChange {
+    pub kind: SymbolChangeKind,
+    pub old: Option<SemanticBlock>,
+    pub new: Option<SemanticBlock>,
+}
+
+impl SymbolChange {
+    /// The block as it is in the new version, or in the old one if it was removed.
+    pub fn block(&self) -> &SemanticBlock {
+        self.new.as_ref().or(self.old.as_ref()).expect("a symbol change has a block")
+    }
+}
+
+/// The symbol changes in one file that differs between two trees.
+#[derive(Debug, Clone)]
+pub struct FileSymbolChanges {
+    pub path: String,
+    pub kind: ChangeKind,
+    /// None if the file's language is not supported or one of its versions is binary.
+    pub symbols: Option<Vec<SymbolChange>>,
+}
+
+/// Identifies a block across versions: its kind, enclosing blocks, name, and how many blocks
+/// with the same kind, enclosing blocks and name come before it.
+type BlockKey<'b> = (&'b str, &'b [String], &'b str, usize);
+
+/// Matches the blocks of two versions of a file and lists the ones that changed.
+///
+/// Blocks are matched by kind, enclosing blocks and name. Blocks left over on both sides with
+/// the same kind and name are matched next, as blocks moved to another enclosing block.
+/// Code is compared line by line ignoring indentation, so re-indenting a moved block does not
+/// count as a modification. Blocks nested in a moved block are not reported separately.
+pub fn diff_blocks(old: &[SemanticBlock], new: &[SemanticBlock]) -> Vec<SymbolChange> {
+    let old_keys = block_keys(old);
+    let new_keys = block_keys(new);
+    let old_index: HashMap<BlockKey<'_>, usize> = old_keys.iter().enumerate().map(|(i, key)| (*key, i)).collect();
+
+    let mut matched = Vec::new();
+    let mut added = Vec::new();
+    for (j, key) in new_keys.iter().enumerate() {
+        match old_index.get(key) {
+            Some(&i) => matched.push((i, j)),
+            None => added.push(j),
+        }
+    }
+    let matched_old: HashSet<usize> = matched.iter().map(|&(i, _)| i).collect();
+    let mut removed: Vec<usize> = (0..old.len()).filter(|i| !matched_old.contains(i)).collect();
+
+    let reordered = reordered(&matched, new);
+    let mut changes = Vec::new();
+    let mut change = |kind, i: Option<usize>, j: Option<usize>| {
+        changes.push(SymbolChange {
+            kind,
+            old: i.map(|i| old[i].clone()),
+            new: j.map(|j| new[j].clone()),
+        })
+    };
+
+    for &(i, j) in &matched {
+        if !same_code(&old[i], &new[j]) {
+            change(SymbolChangeKind::Modified, Some(i), Some(j));
+        } else if reordered.contains(&j) {
+            change(SymbolChangeKind::Moved, Some(i), Some(j));
+        }
+    }
+
+    for j in added {
+        let partner = removed
+            .iter()
+            .position(|&i| old[i].kind == new[j].kind && old[i].name == new[j].name);
+        match partner {
+            Some(position) => {
+                let i = removed.remove(position);
+                let kind = if same_code(&old[i], &new[j]) {
+                    SymbolChangeKind::Moved
+                } else {
+                    SymbolChangeKind::Modified
+                };
+                change(kind, Some(i), Some(j));
+            }
+            None => change(SymbolChangeKind::Added, None, Some(j)),
+        }
+    }
+
+    for i in removed {
+        change(SymbolChangeKind::Removed, Some(i), None);
+    }
+
+    let moved: Vec<SemanticBlock> = changes
+        .iter()
+        .filter(|change| change.kind == SymbolChangeKind::Moved)
+        .filter_map(|change| change.new.clone())
+        .collect();
+    changes.retain(|change| {
+        change.kind != SymbolChangeKind::Moved
+            || !moved.iter().any(|outer| encloses(outer, change.block()))
+    });
+
+    changes.sort_by_key(|change| change.block().start_line);
+    changes
+}
+
+fn block_keys(blocks: &[SemanticBlock]) -> Vec<BlockKey<'_>> {
+    let mut seen: HashMap<(&str, &[String], &str), usize> = HashMap::new();
+    blocks
+        .iter()
+        .map(|block| {
+            let base = (block.kind.as_str(), block.breadcrumbs.as_slice(), block.name.as_str());
+            let count = seen.entry(base).or_default();
+            *count += 1;
+            (base.0, base.1, base.2, *count - 1)
+        })
+        .collect()
+}
+
+/// Indices in `new` of matched blocks whose order among their siblings changed.
+fn reordered(matched: &[(usize, usize)], new: &[SemanticBlock]) -> HashSet<usize> {
+    // Matched blocks have the same enclosing blocks on both sides. `matched` is in new order.
+    let mut siblings: HashMap<&[String], Vec<(usize, usize)>> = HashMap::new();
+    for &(i, j) in matched {
+        siblings.entry(new[j].breadcrumbs.as_slice()).or_default().push((i, j));
+    }
+
+    let mut reordered = HashSet::new();
+    for group in siblings.values() {
+        let new_order: Vec<usize> = group.iter().map(|&(i, _)| i).collect();
+        let mut old_order = new_order.clone();
+        old_order.sort_unstable();
+
+        for op in capture_diff_slices(Algorithm::Myers, &old_order, &new_order) {
+            if let DiffOp::Insert { new_index, new_len, .. } = op {
+                reordered.extend(group[new_index..new_index + new_len].iter().map(|&(_, j)| j));
+            }
+        }
+    }
+    reordered
+}
+
+fn same_code(old: &SemanticBlock, new: &SemanticBlock) -> bool {
+    old.content.lines().map(str::trim).eq(new.content.lines().map(str::trim))
+}
+
+fn encloses(outer: &SemanticBlock, inner: &SemanticBlock) -> bool {
+    inner.breadcrumbs.len() > outer.breadcrumbs.len()
+        && outer.start_line <= inner.start_line
+        && inner.end_line <= outer.end_line
+}
+
+/// Lists the symbols changed between two trees.
+pub struct SemanticDiff<'a> {
+    store: &'a ObjectStore,
+    engine: &'a SemanticEngine,
+}
+
+impl<'a> SemanticDiff<'a> {
+    pub fn new(store: &'a ObjectStore, engine: &'a SemanticEngine) -> Self {
+        Self { store, engine }
+    }
+
+    /// Compares the blocks of every file that differs between two trees, sorted by path.
+    /// A missing tree is treated as empty. When `paths` is not empty, only files at or under
+    /// one of them are compared.
+    pub fn diff_trees(
+        &self,
+        old_tree: Option<&str>,
+        new_tree: Option<&str>,
+        paths: &[String],
+    ) -> Result<Vec<FileSymbolChanges>> {
+        let mut files = Vec::new();
+
+        for change in TreeReader::new(self.store).diff(old_tree, new_tree)? {
+            let selected = paths.is_empty()
+                || paths.iter().any(|path| {
+                    let path = path.trim_end_matches('/');
+                    change.path == path || change.path.starts_with(&format!("{}/", path))
+                });
+            if !selected {
+                continue;
+            }
+
+            let old = self.blocks(&change.path, change.old.as_ref())?;
+            let new = self.blocks(&change.path, change.new.as_ref())?;
+            let symbols = match (old, new) {
+                (Some(old), Some(new)) => Some(diff_blocks(&old, &new)),
+                _ => None,
+            };
+
+            files.push(FileSymbolChanges {
+                path: change.path,
+                kind: change.kind,
+                symbols,
+            });
+        }
+
+        Ok(files)
+    }
+
+    /// The blocks of one version of a file: empty if the file is absent, None if it cannot be
+    /// parsed.
+    fn blocks(&self, path: &str, entry: Option<&DirectoryEntry>) -> Result<Option<Vec<SemanticBlock>>> {
+        let Some(entry) = entry else {
+            return Ok(Some(Vec::new()));
+        };
+        let supported = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.engine.language_for(ext).is_some());
+        if !supported {
+            return Ok(None);
+        }
+
+        let (kind, content) = self.store.read_object(&entry.hash)?;
+        if kind != ObjectType::Blob {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Blob, found {}",
+                kind
+            )));
+        }
+        if Blob::is_binary(&content) {
+            return Ok(None);
+        }
+
+        Ok(self
+            .engine
+            .parse_file(Path::new(path), &content)
+            .map(|(language, tree)| self.engine.extract_blocks(language, &tree, &content)))
+    }
+}
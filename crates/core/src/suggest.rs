@@ -0,0 +1,281 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::objects::snapshot::SnapshotContext;
+use crate::semantic_diff::{FileSymbolChanges, SymbolChange, SymbolChangeKind};
+use crate::{JogenError, Result};
+
+/// A condition on the symbols changed in the parsed files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymbolCondition {
+    /// At least one symbol was added.
+    Added,
+    /// At least one symbol marked public by its language was added.
+    AddedPublic,
+    /// Symbols changed and all of them were modified: none was added, removed or moved.
+    ModifiedOnly,
+    /// Symbols changed and all of them only moved.
+    MovedOnly,
+}
+
+/// A rule proposing a context, e.g.
+///
+/// ```toml
+/// [[suggest.rules]]
+/// context = "docs"
+/// reason = "only documentation changed"
+/// paths = ["docs/**", "*.md"]
+/// ```
+///
+/// All the conditions a rule sets must hold for it to match. A rule without conditions always
+/// matches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestionRule {
+    pub context: String,
+    pub reason: String,
+    /// Glob patterns every changed path must match. `*` also matches `/`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// Words the snapshot message must mention at least one of, case-insensitively.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub message: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbols: Option<SymbolCondition>,
+}
+
+impl SuggestionRule {
+    fn new(context: SnapshotContext, reason: &str) -> Self {
+        Self {
+            context: context.as_str().to_string(),
+            reason: reason.to_string(),
+            paths: Vec::new(),
+            message: Vec::new(),
+            symbols: None,
+        }
+    }
+
+    fn paths(mut self, patterns: &[&str]) -> Self {
+        self.paths = patterns.iter().map(|pattern| pattern.to_string()).collect();
+        self
+    }
+
+    fn message(mut self, words: &[&str]) -> Self {
+        self.message = words.iter().map(|word| word.to_string()).collect();
+        self
+    }
+
+    fn symbols(mut self, condition: SymbolCondition) -> Self {
+        self.symbols = Some(condition);
+        self
+    }
+
+    /// The rules used when the configuration does not turn them off, in the order they are tried.
+    pub fn built_in() -> Vec<Self> {
+        use SnapshotContext::*;
+
+        vec![
+            Self::new(Docs, "only documentation changed").paths(&[
+                "*.md", "*.rst", "*.txt", "*.adoc", "docs/**", "doc/**", "LICENSE*", "AUTHORS*",
+            ]),
+            Self::new(Chore, "only tests changed").paths(&[
+                "tests/**", "test/**", "**/tests/**", "*_test.*", "*.test.*", "*.spec.*", "**/test_*.py",
+            ]),
+            Self::new(Chore, "only configuration and build files changed").paths(&[
+                "*.toml", "*.lock", "*.yml", "*.yaml", "*.json", "*.ini", "*.cfg", ".github/**",
+                ".jogenignore", ".gitignore", "Dockerfile", "Makefile",
+            ]),
+            Self::new(Fix, "the message describes a fix").message(&[
+                "fix", "bug", "crash", "regression", "broken", "hotfix", "patch",
+            ]),
+            Self::new(Refactor, "the message describes a restructure").message(&[
+                "refactor", "rename", "cleanup", "simplify", "extract", "restructure", "reorganize",
+            ]),
+            Self::new(Feature, "new public symbols were added").symbols(SymbolCondition::AddedPublic),
+            Self::new(Refactor, "symbols only moved").symbols(SymbolCondition::MovedOnly),
+            Self::new(Feature, "the message describes new behavior").message(&[
+                "add", "implement", "support", "introduce", "allow", "new",
+            ]),
+            Self::new(Fix, "existing symbols were changed without adding new ones")
+                .symbols(SymbolCondition::ModifiedOnly),
+            Self::new(Feature, "new symbols were added").symbols(SymbolCondition::Added),
+        ]
+    }
+}
+
+/// Configuration of `snapshot --suggest-context`, under `[suggest]` in `.jogen/config.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestConfig {
+    /// Whether the built-in rules are tried after the declared ones.
+    #[serde(default = "default_built_in_rules")]
+    pub built_in_rules: bool,
+    #[serde(default)]
+    pub rules: Vec<SuggestionRule>,
+}
+
+fn default_built_in_rules() -> bool {
+    true
+}
+
+impl Default for SuggestConfig {
+    fn default() -> Self {
+        Self {
+            built_in_rules: true,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl SuggestConfig {
+    pub fn is_default(&self) -> bool {
+        self.built_in_rules && self.rules.is_empty()
+    }
+}
+
+/// A proposed context, with the rule's reason and what triggered it.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub context: SnapshotContext,
+    pub reason: String,
+    pub evidence: Option<String>,
+    /// Whether the rule was declared in the configuration.
+    pub configured: bool,
+}
+
+struct CompiledRule {
+    rule: SuggestionRule,
+    paths: Option<GlobSet>,
+    configured: bool,
+}
+
+/// Proposes a context for a set of changes by trying rules in order: the declared ones first,
+/// then the built-in ones. The first rule whose conditions all hold wins.
+pub struct ContextSuggester {
+    rules: Vec<CompiledRule>,
+}
+
+impl ContextSuggester {
+    pub fn new(config: &SuggestConfig) -> Result<Self> {
+        let built_in = if config.built_in_rules {
+            SuggestionRule::built_in()
+        } else {
+            Vec::new()
+        };
+
+        let mut rules = Vec::new();
+        for (rule, configured) in config
+            .rules
+            .iter()
+            .cloned()
+            .map(|rule| (rule, true))
+            .chain(built_in.into_iter().map(|rule| (rule, false)))
+        {
+            let paths = if rule.paths.is_empty() {
+                None
+            } else {
+                let mut builder = GlobSetBuilder::new();
+                for pattern in &rule.paths {
+                    let glob = Glob::new(pattern).map_err(|e| {
+                        JogenError::InvalidContext(format!("suggestion rule for '{}': {}", rule.context, e))
+                    })?;
+                    builder.add(glob);
+                }
+                Some(builder.build().map_err(|e| {
+                    JogenError::InvalidContext(format!("suggestion rule for '{}': {}", rule.context, e))
+                })?)
+            };
+            rules.push(CompiledRule {
+                rule,
+                paths,
+                configured,
+            });
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Proposes a context for the files changed since the last snapshot and the message, if any.
+    pub fn suggest(&self, files: &[FileSymbolChanges], message: Option<&str>) -> Option<Suggestion> {
+        let words: Vec<String> = message
+            .unwrap_or_default()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        let symbols: Vec<(&str, &SymbolChange)> = files
+            .iter()
+            .flat_map(|file| {
+                file.symbols
+                    .iter()
+                    .flatten()
+                    .map(move |change| (file.path.as_str(), change))
+            })
+            .collect();
+
+        self.rules.iter().find_map(|compiled| {
+            let rule = &compiled.rule;
+            let mut evidence = Vec::new();
+
+            if let Some(paths) = &compiled.paths {
+                if files.is_empty() || !files.iter().all(|file| paths.is_match(&file.path)) {
+                    return None;
+                }
+                evidence.push(format!("{} file(s) changed", files.len()));
+            }
+
+            if !rule.message.is_empty() {
+                let word = rule.message.iter().find_map(|keyword| {
+                    let keyword = keyword.to_lowercase();
+                    words.iter().find(|word| mentions(word, &keyword))
+                })?;
+                evidence.push(format!("message mentions '{}'", word));
+            }
+
+            if let Some(condition) = rule.symbols {
+                evidence.push(Self::check_symbols(condition, &symbols)?);
+            }
+
+            Some(Suggestion {
+                context: SnapshotContext::from(rule.context.as_str()),
+                reason: rule.reason.clone(),
+                evidence: (!evidence.is_empty()).then(|| evidence.join(", ")),
+                configured: compiled.configured,
+            })
+        })
+    }
+
+    /// Checks a symbol condition, returning a description of the symbols that satisfy it.
+    fn check_symbols(condition: SymbolCondition, symbols: &[(&str, &SymbolChange)]) -> Option<String> {
+        let describe = |(path, change): &(&str, &SymbolChange)| {
+            let block = change.block();
+            let mut names = block.breadcrumbs.clone();
+            names.push(block.name.clone());
+            format!("{} in {}", names.join(" > "), path)
+        };
+        let only = |kind: SymbolChangeKind| -> Option<String> {
+            let all = !symbols.is_empty() && symbols.iter().all(|(_, change)| change.kind == kind);
+            all.then(|| format!("{} symbol(s), e.g. {}", symbols.len(), describe(&symbols[0])))
+        };
+
+        match condition {
+            SymbolCondition::Added => symbols
+                .iter()
+                .find(|(_, change)| change.kind == SymbolChangeKind::Added)
+                .map(describe),
+            SymbolCondition::AddedPublic => symbols
+                .iter()
+                .find(|(_, change)| change.kind == SymbolChangeKind::Added && change.block().public)
+                .map(describe),
+            SymbolCondition::ModifiedOnly => only(SymbolChangeKind::Modified),
+            SymbolCondition::MovedOnly => only(SymbolChangeKind::Moved),
+        }
+    }
+}
+
+/// Whether a message word is the keyword or one of its inflections, e.g. `fixes` or `added`.
+fn mentions(word: &str, keyword: &str) -> bool {
+    match word.strip_prefix(keyword) {
+        Some(suffix) => matches!(suffix, "" | "s" | "es" | "d" | "ed" | "ing" | "ded" | "ped"),
+        None => false,
+    }
+}
@@ -0,0 +1,278 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use tempfile::NamedTempFile;
+
+use crate::object_store::{ObjectStore, ObjectType};
+use crate::objects::blob::Blob;
+use crate::objects::directory::{Directory, DirectoryEntry, EntryMode};
+use crate::semantic::{SemanticBlock, SemanticEngine};
+use crate::{JogenError, Result};
+
+const SYMBOL_INDEX_MAGIC: &str = "JGSI 1";
+
+/// A block defined in a file of a snapshot.
+#[derive(Debug, Clone)]
+pub struct IndexedSymbol {
+    /// `/`-separated path of the file, relative to the tree root.
+    pub path: String,
+    pub kind: String,
+    pub name: String,
+    pub breadcrumbs: Vec<String>,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub public: bool,
+}
+
+impl IndexedSymbol {
+    /// The symbol as a block without content, e.g. to match it with a `SymbolPath`.
+    pub fn to_block(&self) -> SemanticBlock {
+        SemanticBlock {
+            kind: self.kind.clone(),
+            name: self.name.clone(),
+            content: String::new(),
+            start_line: self.start_line,
+            end_line: self.end_line,
+            breadcrumbs: self.breadcrumbs.clone(),
+            public: self.public,
+        }
+    }
+}
+
+/// Counts of directory indexes read from the cache and built while listing symbols.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IndexStats {
+    pub reused: usize,
+    pub built: usize,
+}
+
+/// Symbols of snapshot trees, cached in `.jogen/symbol-index/<engine fingerprint>/`.
+///
+/// Each directory hash has its own cache file listing the blocks of the files directly in that
+/// directory; subdirectories have their own files. Since a directory hash only changes when
+/// its contents do, a new snapshot only indexes the directories on the paths to changed files,
+/// and listing the symbols of an old snapshot only reads cache files. The cache is keyed by the
+/// engine's fingerprint, so changing a language's block query starts a new cache.
+///
+/// Each file is zstd-compressed text: the line `JGSI 1`, then one line per symbol with
+/// tab-separated file name, kind, start line, end line, `1` if public, breadcrumbs joined by
+/// `\x1f`, and name.
+pub struct SymbolIndex<'a> {
+    store: &'a ObjectStore,
+    engine: &'a SemanticEngine,
+    cache_path: PathBuf,
+}
+
+impl<'a> SymbolIndex<'a> {
+    pub fn new(store: &'a ObjectStore, engine: &'a SemanticEngine, root_path: &Path) -> Self {
+        let cache_path = root_path
+            .join(".jogen/symbol-index")
+            .join(&engine.fingerprint()[..16]);
+        Self {
+            store,
+            engine,
+            cache_path,
+        }
+    }
+
+    /// Lists every symbol in a tree, sorted by path and line, indexing the directories that are
+    /// not cached yet.
+    pub fn symbols(&self, tree_hash: &str) -> Result<(Vec<IndexedSymbol>, IndexStats)> {
+        let mut symbols = Vec::new();
+        let mut stats = IndexStats::default();
+        let mut pending = vec![(String::new(), tree_hash.to_string())];
+
+        while let Some((prefix, hash)) = pending.pop() {
+            let directory = self.load_directory(&hash)?;
+
+            let cached = self.read_cache(&hash)?;
+            let own = match cached {
+                Some(own) => {
+                    stats.reused += 1;
+                    own
+                }
+                None => {
+                    let own = self.index_directory(&directory)?;
+                    self.write_cache(&hash, &own)?;
+                    stats.built += 1;
+                    own
+                }
+            };
+
+            symbols.extend(own.into_iter().map(|mut symbol| {
+                symbol.path = format!("{}{}", prefix, symbol.path);
+                symbol
+            }));
+
+            for entry in directory.entries() {
+                if entry.mode == EntryMode::Directory {
+                    pending.push((format!("{}{}/", prefix, entry.name), entry.hash.clone()));
+                }
+            }
+        }
+
+        symbols.sort_by(|a, b| a.path.cmp(&b.path).then(a.start_line.cmp(&b.start_line)));
+        Ok((symbols, stats))
+    }
+
+    /// Extracts the blocks of the files directly in a directory. Paths are file names.
+    fn index_directory(&self, directory: &Directory) -> Result<Vec<IndexedSymbol>> {
+        let files: Vec<&DirectoryEntry> = directory
+            .entries()
+            .iter()
+            .filter(|entry| entry.mode != EntryMode::Directory)
+            .filter(|entry| {
+                Path::new(&entry.name)
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| self.engine.language_for(ext).is_some())
+            })
+            .collect();
+
+        let per_file: Vec<Vec<IndexedSymbol>> = files
+            .par_iter()
+            .map(|entry| self.index_file(entry))
+            .collect::<Result<_>>()?;
+
+        Ok(per_file.into_iter().flatten().collect())
+    }
+
+    fn index_file(&self, entry: &DirectoryEntry) -> Result<Vec<IndexedSymbol>> {
+        let (kind, content) = self.store.read_object(&entry.hash)?;
+        if kind != ObjectType::Blob {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Blob, found {}",
+                kind
+            )));
+        }
+        if Blob::is_binary(&content) {
+            return Ok(Vec::new());
+        }
+
+        let Some((language, tree)) = self.engine.parse_file(Path::new(&entry.name), &content) else {
+            return Ok(Vec::new());
+        };
+
+        Ok(self
+            .engine
+            .extract_blocks(language, &tree, &content)
+            .into_iter()
+            .map(|block| IndexedSymbol {
+                path: entry.name.clone(),
+                kind: block.kind,
+                name: block.name,
+                breadcrumbs: block.breadcrumbs,
+                start_line: block.start_line,
+                end_line: block.end_line,
+                public: block.public,
+            })
+            .collect())
+    }
+
+    fn cache_file(&self, hash: &str) -> PathBuf {
+        let (subdir, filename) = hash.split_at(2);
+        self.cache_path.join(subdir).join(filename)
+    }
+
+    /// Reads a directory's cached symbols. A missing or unreadable file is a cache miss.
+    fn read_cache(&self, hash: &str) -> Result<Option<Vec<IndexedSymbol>>> {
+        let path = self.cache_file(hash);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let mut text = String::new();
+        let readable = fs::File::open(&path)
+            .and_then(zstd::stream::Decoder::new)
+            .and_then(|mut decoder| decoder.read_to_string(&mut text))
+            .is_ok();
+        if !readable {
+            return Ok(None);
+        }
+
+        let mut lines = text.lines();
+        if lines.next() != Some(SYMBOL_INDEX_MAGIC) {
+            return Ok(None);
+        }
+
+        let mut symbols = Vec::new();
+        for line in lines {
+            let Some(symbol) = Self::parse_line(line) else {
+                return Ok(None);
+            };
+            symbols.push(symbol);
+        }
+        Ok(Some(symbols))
+    }
+
+    fn parse_line(line: &str) -> Option<IndexedSymbol> {
+        let mut fields = line.splitn(7, '\t');
+        let path = fields.next()?.to_string();
+        let kind = fields.next()?.to_string();
+        let start_line = fields.next()?.parse().ok()?;
+        let end_line = fields.next()?.parse().ok()?;
+        let public = fields.next()? == "1";
+        let breadcrumbs = fields.next()?;
+        let name = fields.next()?.to_string();
+
+        Some(IndexedSymbol {
+            path,
+            kind,
+            name,
+            breadcrumbs: if breadcrumbs.is_empty() {
+                Vec::new()
+            } else {
+                breadcrumbs.split('\x1f').map(str::to_string).collect()
+            },
+            start_line,
+            end_line,
+            public,
+        })
+    }
+
+    fn write_cache(&self, hash: &str, symbols: &[IndexedSymbol]) -> Result<()> {
+        // Separators inside names would break the line format.
+        let clean = |text: &str| text.replace(['\t', '\n', '\r', '\x1f'], " ");
+
+        let mut text = String::from(SYMBOL_INDEX_MAGIC);
+        text.push('\n');
+        for symbol in symbols {
+            let breadcrumbs: Vec<String> = symbol.breadcrumbs.iter().map(|name| clean(name)).collect();
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                clean(&symbol.path),
+                symbol.kind,
+                symbol.start_line,
+                symbol.end_line,
+                if symbol.public { "1" } else { "0" },
+                breadcrumbs.join("\x1f"),
+                clean(&symbol.name)
+            ));
+        }
+
+        let path = self.cache_file(hash);
+        let dir_path = path.parent().unwrap_or(&self.cache_path);
+        fs::create_dir_all(dir_path).map_err(JogenError::Io)?;
+        let file = NamedTempFile::new_in(dir_path).map_err(JogenError::Io)?;
+        let file = {
+            let mut encoder = zstd::stream::Encoder::new(file, 1).map_err(JogenError::Io)?;
+            encoder.write_all(text.as_bytes()).map_err(JogenError::Io)?;
+            encoder.finish().map_err(JogenError::Io)?
+        };
+        file.persist(&path).map_err(|e| JogenError::Io(e.error))?;
+        Ok(())
+    }
+
+    fn load_directory(&self, hash: &str) -> Result<Directory> {
+        let (kind, content) = self.store.read_object(hash)?;
+        if kind != ObjectType::Directory {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Dir, found {}",
+                kind
+            )));
+        }
+        Directory::parse(&content)
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use crate::object_store::{ObjectStore, ObjectType};
+use crate::objects::directory::{Directory, DirectoryEntry, EntryMode};
+use crate::{JogenError, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Deleted,
+    Modified,
+}
+
+impl ChangeKind {
+    /// Single-letter code used when listing changes.
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::Added => 'A',
+            Self::Deleted => 'D',
+            Self::Modified => 'M',
+        }
+    }
+}
+
+/// A file that differs between two trees. Directories are never reported themselves, only
+/// the files inside them.
+#[derive(Debug, Clone)]
+pub struct TreeChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub old: Option<DirectoryEntry>,
+    pub new: Option<DirectoryEntry>,
+}
+
+/// Read-only queries over directory trees stored in the object store.
+pub struct TreeReader<'a> {
+    store: &'a ObjectStore,
+}
+
+impl<'a> TreeReader<'a> {
+    pub fn new(store: &'a ObjectStore) -> Self {
+        Self { store }
+    }
+
+    /// Lists every file that differs between two trees, sorted by path.
+    /// A missing tree is treated as empty. Subtrees with the same hash are skipped entirely.
+    pub fn diff(&self, old_tree: Option<&str>, new_tree: Option<&str>) -> Result<Vec<TreeChange>> {
+        let mut changes = Vec::new();
+        self.diff_into(old_tree, new_tree, "", &mut changes)?;
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(changes)
+    }
+
+    /// Finds the entry at a `/`-separated path relative to the tree root.
+    pub fn lookup(&self, tree_hash: &str, path: &str) -> Result<Option<DirectoryEntry>> {
+        let mut current = tree_hash.to_string();
+        let mut components = path.split('/').filter(|c| !c.is_empty()).peekable();
+        let mut found = None;
+
+        while let Some(component) = components.next() {
+            let dir = self.load_directory(&current)?;
+            let Some(entry) = dir.entries().iter().find(|e| e.name == component) else {
+                return Ok(None);
+            };
+
+            if components.peek().is_some() {
+                if entry.mode != EntryMode::Directory {
+                    return Ok(None);
+                }
+                current = entry.hash.clone();
+            } else {
+                found = Some(entry.clone());
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Lists every file in a tree with its path, sorted by path.
+    pub fn files(&self, tree_hash: &str) -> Result<Vec<(String, DirectoryEntry)>> {
+        Ok(self
+            .diff(None, Some(tree_hash))?
+            .into_iter()
+            .filter_map(|change| change.new.map(|entry| (change.path, entry)))
+            .collect())
+    }
+
+    fn diff_into(
+        &self,
+        old_tree: Option<&str>,
+        new_tree: Option<&str>,
+        prefix: &str,
+        changes: &mut Vec<TreeChange>,
+    ) -> Result<()> {
+        if old_tree == new_tree {
+            return Ok(());
+        }
+
+        let mut entries: BTreeMap<String, (Option<DirectoryEntry>, Option<DirectoryEntry>)> =
+            BTreeMap::new();
+        for entry in self.entries(old_tree)? {
+            let name = entry.name.clone();
+            entries.entry(name).or_default().0 = Some(entry);
+        }
+        for entry in self.entries(new_tree)? {
+            let name = entry.name.clone();
+            entries.entry(name).or_default().1 = Some(entry);
+        }
+
+        for (name, (old, new)) in entries {
+            let path = if prefix.is_empty() {
+                name
+            } else {
+                format!("{}/{}", prefix, name)
+            };
+
+            let old_dir = old.as_ref().filter(|e| e.mode == EntryMode::Directory);
+            let new_dir = new.as_ref().filter(|e| e.mode == EntryMode::Directory);
+            let old_file = old.as_ref().filter(|e| e.mode != EntryMode::Directory);
+            let new_file = new.as_ref().filter(|e| e.mode != EntryMode::Directory);
+
+            if old_dir.is_some() || new_dir.is_some() {
+                self.diff_into(
+                    old_dir.map(|e| e.hash.as_str()),
+                    new_dir.map(|e| e.hash.as_str()),
+                    &path,
+                    changes,
+                )?;
+            }
+
+            let kind = match (old_file, new_file) {
+                (Some(o), Some(n)) if o.hash == n.hash && o.mode == n.mode => continue,
+                (Some(_), Some(_)) => ChangeKind::Modified,
+                (Some(_), None) => ChangeKind::Deleted,
+                (None, Some(_)) => ChangeKind::Added,
+                (None, None) => continue,
+            };
+
+            changes.push(TreeChange {
+                path,
+                kind,
+                old: old_file.cloned(),
+                new: new_file.cloned(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn entries(&self, tree: Option<&str>) -> Result<Vec<DirectoryEntry>> {
+        match tree {
+            Some(hash) => Ok(self.load_directory(hash)?.entries().to_vec()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn load_directory(&self, hash: &str) -> Result<Directory> {
+        let (kind, content) = self.store.read_object(hash)?;
+        if kind != ObjectType::Directory {
+            return Err(JogenError::ObjectCorrupt(format!(
+                "Expected Dir, found {}",
+                kind
+            )));
+        }
+        Directory::parse(&content)
+    }
+}